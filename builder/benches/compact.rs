@@ -0,0 +1,61 @@
+// Compares building a small message through the default `Option` storage
+// against `#[builder(compact)]`. Run with `cargo bench -p derive_builder`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use derive_builder::Builder;
+use std::mem;
+
+#[derive(Builder)]
+pub struct Message {
+    pub id: u32,
+    pub kind: u8,
+    pub flags: u8,
+    pub len: u16,
+    pub seq: Option<u32>,
+}
+
+#[derive(Builder)]
+#[builder(compact)]
+pub struct CompactMessage {
+    pub id: u32,
+    pub kind: u8,
+    pub flags: u8,
+    pub len: u16,
+    pub seq: Option<u32>,
+}
+
+fn build(c: &mut Criterion) {
+    println!(
+        "size_of: MessageBuilder = {}, CompactMessageBuilder = {}",
+        mem::size_of::<MessageBuilder>(),
+        mem::size_of::<CompactMessageBuilder>(),
+    );
+
+    let mut group = c.benchmark_group("build");
+    group.bench_function("option", |b| {
+        b.iter(|| {
+            Message::builder()
+                .id(black_box(1))
+                .kind(black_box(2))
+                .flags(black_box(3))
+                .len(black_box(4))
+                .build()
+                .unwrap()
+        })
+    });
+    group.bench_function("compact", |b| {
+        b.iter(|| {
+            CompactMessage::builder()
+                .id(black_box(1))
+                .kind(black_box(2))
+                .flags(black_box(3))
+                .len(black_box(4))
+                .build()
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, build);
+criterion_main!(benches);
@@ -0,0 +1,54 @@
+use proc_macro2::Ident;
+use proc_macro_error::emit_error;
+use quote::format_ident;
+use syn::{Attribute, Data, Field, Fields, Generics, Visibility};
+
+use crate::Ast;
+
+#[derive(Debug)]
+pub struct Model {
+    pub target: Ident,
+    pub builder: Ident,
+    pub vis: Visibility,
+    pub attrs: Vec<Attribute>,
+    pub generics: Generics,
+    pub fields: Vec<Field>,
+}
+
+/// Returns `None` for input that no builder can be generated for, after
+/// reporting why.
+pub fn analyze(ast: Ast) -> Option<Model> {
+    let item = ast;
+
+    let mut parsed: Vec<Field> = vec![];
+
+    let target = item.ident.clone();
+    let builder = format_ident!("{}Builder", target);
+
+    match item.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                for f in fields.named.iter() {
+                    parsed.push(f.clone());
+                }
+            }
+            Fields::Unit => {}
+            Fields::Unnamed(ref fields) => {
+                emit_error!(fields, "`Builder` needs a struct with named fields");
+                return None;
+            }
+        },
+        _ => {
+            emit_error!(target, "`Builder` can only be derived for structs");
+            return None;
+        }
+    }
+    Some(Model {
+        target,
+        builder,
+        vis: item.vis,
+        attrs: item.attrs,
+        generics: item.generics,
+        fields: parsed,
+    })
+}
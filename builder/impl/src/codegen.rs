@@ -0,0 +1,1364 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::ext::IdentExt;
+use syn::{GenericParam, Type};
+
+use crate::lower::{mentions, DefaultWith, FieldIr, GroupIr, Ir, SetOnce, Slot, Wrapper};
+
+pub type Rust = proc_macro::TokenStream;
+
+pub fn codegen(ir: Ir) -> Rust {
+    let Ir {
+        target,
+        builder,
+        vis,
+        generics,
+        fields,
+        groups,
+        required_in_constructor,
+        ..
+    } = &ir;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // Required fields passed to `Target::builder(...)` are always set, so
+    // they get no setters and can't make `build()` fail.
+    let positional: Vec<_> = fields
+        .iter()
+        .filter(|f| *required_in_constructor && f.required)
+        .collect();
+
+    let builder_fields = fields
+        .iter()
+        .filter(|f| f.slot == Slot::Option)
+        .map(to_builder_field);
+    let set_once = fields.iter().any(|f| f.set_once.is_some());
+    let setters = fields
+        .iter()
+        .filter(|f| !positional.iter().any(|p| p.name == f.name) && f.generic_setter.is_none())
+        .flat_map(|f| {
+            let mut setters = if ir.const_builder {
+                vec![to_const_setter(&ir, f)]
+            } else {
+                to_setter(f)
+            };
+            setters.extend(to_alias_setters(&ir, f));
+            setters
+        });
+    let checks = fields
+        .iter()
+        .flat_map(|f| to_constraint_checks(f, fields))
+        .chain(groups.iter().map(|g| to_group_check(g, fields)));
+    let bindings = ir
+        .resolve_order
+        .iter()
+        .map(|&i| to_build_binding(&fields[i], &positional));
+    let names = fields.iter().map(|f| {
+        let FieldIr { name, cfgs, .. } = f;
+        quote!(#( #cfgs )* #name)
+    });
+    let field_infos = fields.iter().map(to_field_info);
+    let from_env = to_from_env(&ir);
+    let set_by_name = to_set_by_name(&ir);
+    let patch = to_patch(&ir);
+    let build_const = to_build_const(&ir);
+
+    // Fields marked `set_once` that were set again, reported by `build()`.
+    let (set_twice_field, set_twice_check) = if set_once {
+        (
+            quote!(__set_twice: ::std::vec::Vec<&'static str>,),
+            quote! {
+                if let ::core::option::Option::Some(field) = self.__set_twice.first() {
+                    return ::core::result::Result::Err(::derive_builder::BuildError::SetTwice {
+                        field,
+                    });
+                }
+            },
+        )
+    } else {
+        (quote!(), quote!())
+    };
+
+    let build = if *required_in_constructor {
+        quote! {
+            /// Builds the target. Required fields were passed to the
+            /// constructor, so this only panics if called a second time.
+            pub fn build(&mut self) -> #target #ty_generics {
+                #( #bindings )*
+                #target {
+                    #( #names ),*
+                }
+            }
+        }
+    } else {
+        quote! {
+            pub fn build(&mut self) -> ::core::result::Result<#target #ty_generics, ::derive_builder::BuildError> {
+                #set_twice_check
+                #( #checks )*
+                #( #bindings )*
+                ::core::result::Result::Ok(#target {
+                    #( #names ),*
+                })
+            }
+        }
+    };
+
+    let target_impl = to_target_impl(&ir, &positional);
+    let traits = to_trait_impls(&ir, &positional);
+    let generic_setters = fields.iter().filter_map(|f| to_generic_setter(&ir, f));
+
+    let compact_field = match ir.compact {
+        Some(word_bits) => {
+            let slots = to_compact_ty(&ir, word_bits);
+            quote!(__slots: #slots,)
+        }
+        None => quote!(),
+    };
+
+    let expanded = quote! {
+        #vis struct #builder #generics #where_clause {
+            #( #builder_fields, )*
+            #set_twice_field
+            #compact_field
+        }
+
+        impl #impl_generics #builder #ty_generics #where_clause {
+            /// Describes every field of the target, in declaration order.
+            pub const FIELDS: &'static [::derive_builder::FieldInfo] = &[
+                #( #field_infos ),*
+            ];
+
+            #( #setters )*
+
+            #( #generic_setters )*
+
+            #build
+        }
+
+        #target_impl
+
+        #traits
+
+        #from_env
+
+        #set_by_name
+
+        #patch
+
+        #build_const
+    };
+
+    expanded.into()
+}
+
+fn to_from_env(ir: &Ir) -> TokenStream {
+    let Ir {
+        builder,
+        generics,
+        fields,
+        required_in_constructor,
+        ..
+    } = ir;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let env_fills: Vec<_> = fields.iter().filter_map(to_env_fill).collect();
+    if env_fills.is_empty() {
+        return quote!();
+    }
+
+    let empty = to_builder_init(ir, &[]);
+    // There is nothing to pass a positional constructor here.
+    let from_env = if *required_in_constructor {
+        quote!()
+    } else {
+        quote! {
+            /// Creates a builder with every `#[builder(env = "...")]` field
+            /// filled from the environment.
+            pub fn from_env() -> ::core::result::Result<Self, ::derive_builder::EnvError> {
+                let mut builder = #empty;
+                builder.fill_from_env()?;
+                ::core::result::Result::Ok(builder)
+            }
+        }
+    };
+
+    quote! {
+        impl #impl_generics #builder #ty_generics #where_clause {
+            #from_env
+
+            /// Fills every `#[builder(env = "...")]` field that is still
+            /// unset from its environment variable, if present.
+            pub fn fill_from_env(&mut self) -> ::core::result::Result<&mut Self, ::derive_builder::EnvError> {
+                #( #env_fills )*
+                ::core::result::Result::Ok(self)
+            }
+        }
+    }
+}
+
+fn to_set_by_name(ir: &Ir) -> TokenStream {
+    let Ir {
+        builder,
+        generics,
+        fields,
+        set_by_name,
+        required_in_constructor,
+        ..
+    } = ir;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    if !set_by_name {
+        return quote!();
+    }
+
+    let arms = fields
+        .iter()
+        .filter(|f| !f.skip_by_name)
+        .map(to_set_by_name_arm);
+    let empty = to_builder_init(ir, &[]);
+
+    let from_pairs = if *required_in_constructor {
+        quote!()
+    } else {
+        quote! {
+            /// Creates a builder from `key=value` style pairs, applying
+            /// them in order through `set_by_name()`.
+            pub fn from_pairs<I, K, V>(pairs: I) -> ::core::result::Result<Self, ::derive_builder::SetError>
+            where
+                I: ::core::iter::IntoIterator<Item = (K, V)>,
+                K: ::core::convert::AsRef<str>,
+                V: ::core::convert::AsRef<str>,
+            {
+                let mut builder = #empty;
+                for (field, value) in pairs {
+                    builder.set_by_name(field.as_ref(), value.as_ref())?;
+                }
+                ::core::result::Result::Ok(builder)
+            }
+        }
+    };
+
+    quote! {
+        impl #impl_generics #builder #ty_generics #where_clause {
+            /// Parses `value` into the field called `field`, or one of its
+            /// `#[builder(key = "...")]` aliases. Repeated fields append.
+            pub fn set_by_name(
+                &mut self,
+                field: &str,
+                value: &str,
+            ) -> ::core::result::Result<(), ::derive_builder::SetError> {
+                match field {
+                    #( #arms )*
+                    _ => {
+                        return ::core::result::Result::Err(::derive_builder::SetError::UnknownField {
+                            field: ::std::string::ToString::to_string(field),
+                        })
+                    }
+                }
+                ::core::result::Result::Ok(())
+            }
+
+            #from_pairs
+        }
+    }
+}
+
+fn to_patch(ir: &Ir) -> TokenStream {
+    let Ir {
+        target,
+        builder,
+        generics,
+        fields,
+        patch,
+        ..
+    } = ir;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    if !patch {
+        return quote!();
+    }
+
+    let empty = to_builder_init(ir, &[]);
+    let applies = fields.iter().map(|f| {
+        let FieldIr {
+            name, cfgs, span, ..
+        } = f;
+        let get = to_slot_get(f, &quote!(self));
+        quote_spanned! {*span=>
+            #( #cfgs )*
+            if let ::core::option::Option::Some(value) = #get {
+                target.#name = ::core::clone::Clone::clone(value);
+            }
+        }
+    });
+    let appends = fields.iter().map(|f| {
+        let FieldIr {
+            name, cfgs, span, ..
+        } = f;
+        let append = if f.each.is_some() {
+            quote_spanned! {*span=>
+                target.#name.extend(::core::iter::IntoIterator::into_iter(value.clone()))
+            }
+        } else {
+            quote_spanned! {*span=>
+                target.#name = ::core::clone::Clone::clone(value)
+            }
+        };
+        let get = to_slot_get(f, &quote!(self));
+        quote_spanned! {*span=>
+            #( #cfgs )*
+            if let ::core::option::Option::Some(value) = #get {
+                #append;
+            }
+        }
+    });
+    let diffs = fields.iter().map(|f| {
+        let FieldIr {
+            name, cfgs, span, ..
+        } = f;
+        let store = to_slot_store(
+            f,
+            &quote!(patch),
+            quote!(::core::clone::Clone::clone(&new.#name)),
+        );
+        quote_spanned! {*span=>
+            #( #cfgs )*
+            if old.#name != new.#name {
+                #store
+            }
+        }
+    });
+
+    quote! {
+        impl #impl_generics #builder #ty_generics #where_clause {
+            /// Overwrites every field of `target` that was set on this
+            /// builder. Repeated fields replace the target's collection.
+            pub fn apply_to(&self, target: &mut #target #ty_generics) {
+                #( #applies )*
+            }
+
+            /// Like `apply_to()`, except that repeated fields extend the
+            /// target's collection instead of replacing it.
+            pub fn append_to(&self, target: &mut #target #ty_generics) {
+                #( #appends )*
+            }
+
+            /// Creates a patch holding every field that differs between `old`
+            /// and `new`, so that `diff(old, new).apply_to(old)` makes `old`
+            /// equal to `new`.
+            pub fn diff(old: &#target #ty_generics, new: &#target #ty_generics) -> Self {
+                let mut patch = #empty;
+                #( #diffs )*
+                patch
+            }
+        }
+    }
+}
+
+/// `build_const()`, which reports what `build()` would return as an error by
+/// panicking, so that it fails the build when evaluated at compile time.
+fn to_build_const(ir: &Ir) -> TokenStream {
+    let Ir {
+        target,
+        builder,
+        generics,
+        fields,
+        groups,
+        const_builder,
+        ..
+    } = ir;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    if !const_builder {
+        return quote!();
+    }
+    let constness = to_constness(ir);
+
+    let names: Vec<_> = fields
+        .iter()
+        .map(|f| {
+            let FieldIr { name, cfgs, .. } = f;
+            quote!(#( #cfgs )* #name)
+        })
+        .collect();
+
+    let mut checks = vec![];
+    for f in fields {
+        let name = &f.name;
+        for other in &f.requires {
+            let other = field_named(fields, other);
+            let (other_name, cfgs, other_cfgs) = (&other.name, &f.cfgs, &other.cfgs);
+            let msg = format!(
+                "field '{}' requires field '{}' to be set",
+                name.unraw(),
+                other_name.unraw()
+            );
+            checks.push(quote_spanned! {f.span=>
+                #( #cfgs )*
+                #( #other_cfgs )*
+                if #name.is_some() && #other_name.is_none() {
+                    ::core::panic!(#msg);
+                }
+            });
+        }
+        for other in &f.conflicts_with {
+            let other = field_named(fields, other);
+            let (other_name, cfgs, other_cfgs) = (&other.name, &f.cfgs, &other.cfgs);
+            let msg = format!(
+                "field '{}' conflicts with field '{}'",
+                name.unraw(),
+                other_name.unraw()
+            );
+            checks.push(quote_spanned! {f.span=>
+                #( #cfgs )*
+                #( #other_cfgs )*
+                if #name.is_some() && #other_name.is_some() {
+                    ::core::panic!(#msg);
+                }
+            });
+        }
+    }
+    for GroupIr { name, exactly_one } in groups {
+        let members: Vec<_> = exactly_one.iter().map(|f| field_named(fields, f)).collect();
+        let msg = format!(
+            "exactly one of {:?} must be set for group '{}'",
+            members
+                .iter()
+                .map(|f| f.name.unraw().to_string())
+                .collect::<Vec<_>>(),
+            name
+        );
+        let counts = members.iter().map(|f| {
+            let FieldIr { name, cfgs, .. } = f;
+            quote!(#( #cfgs )* { __set_count += #name.is_some() as usize; })
+        });
+        // The fields are unpacked into locals by now, so the counter needs a
+        // name that no field can have.
+        checks.push(quote! {
+            {
+                let mut __set_count = 0;
+                #( #counts )*
+                if __set_count != 1 {
+                    ::core::panic!(#msg);
+                }
+            }
+        });
+    }
+
+    let bindings = fields.iter().map(|f| {
+        let FieldIr {
+            name,
+            cfgs,
+            span,
+            required,
+            ..
+        } = f;
+        let none = if *required {
+            let msg = format!("missing value for field '{}'", name.unraw());
+            quote_spanned!(*span=> ::core::panic!(#msg))
+        } else {
+            quote_spanned!(*span=> ::core::option::Option::None)
+        };
+        quote_spanned! {*span=>
+            #( #cfgs )*
+            let #name = match #name {
+                ::core::option::Option::Some(value) => value,
+                ::core::option::Option::None => #none,
+            };
+        }
+    });
+
+    // A field that needs dropping would make the setters fail to compile
+    // with a less helpful error. Types that use the struct's parameters
+    // can't be checked outside of its impl, and are left to that error.
+    let params: Vec<Ident> = generics
+        .params
+        .iter()
+        .map(|p| match p {
+            GenericParam::Type(t) => t.ident.clone(),
+            GenericParam::Lifetime(l) => l.lifetime.ident.clone(),
+            GenericParam::Const(c) => c.ident.clone(),
+        })
+        .collect();
+    let drop_checks = fields
+        .iter()
+        .filter(|f| {
+            let ty = f.field_ty.to_token_stream();
+            ir.const_fns && !params.iter().any(|p| mentions(ty.clone(), p))
+        })
+        .map(|f| {
+            let FieldIr {
+                name,
+                field_ty,
+                cfgs,
+                span,
+                ..
+            } = f;
+            let msg = format!(
+                "field `{}` of a `#[builder(const)]` struct can't have a type that needs dropping",
+                name.unraw()
+            );
+            quote_spanned! {*span=>
+                #( #cfgs )*
+                const _: () = ::core::assert!(!::core::mem::needs_drop::<#field_ty>(), #msg);
+            }
+        });
+
+    quote! {
+        #( #drop_checks )*
+
+        impl #impl_generics #builder #ty_generics #where_clause {
+            /// Like `build()`, but usable in constants and statics. Panics
+            /// where `build()` would return an error, which fails the build
+            /// when evaluated at compile time.
+            pub #constness fn build_const(self) -> #target #ty_generics {
+                let #builder { #( #names ),* } = self;
+                #( #checks )*
+                #( #bindings )*
+                #target { #( #names ),* }
+            }
+        }
+    }
+}
+
+/// The `derive_builder::compact::Compact` a compact builder keeps its fields
+/// in, which tracks which of them are set and drops those.
+fn to_compact_ty(ir: &Ir, word_bits: usize) -> TokenStream {
+    let word = format_ident!("u{}", word_bits);
+    let tys = ir.fields.iter().map(|f| &f.field_ty);
+    quote!(::derive_builder::compact::Compact<( #( #tys, )* ), #word>)
+}
+
+/// `Target::builder()`. Type parameters that have a `generic_setter` start
+/// out as `derive_builder::Unset` and get replaced when that setter is called.
+fn to_target_impl(ir: &Ir, positional: &[&FieldIr]) -> TokenStream {
+    let Ir {
+        target,
+        builder,
+        generics,
+        fields,
+        ..
+    } = ir;
+
+    let is_generic_setter = |param: &GenericParam| match param {
+        GenericParam::Type(t) => fields
+            .iter()
+            .any(|f| f.generic_setter.as_ref() == Some(&t.ident)),
+        _ => false,
+    };
+
+    let mut impl_generics = generics.clone();
+    impl_generics.params = generics
+        .params
+        .iter()
+        .filter(|p| !is_generic_setter(p))
+        .cloned()
+        .collect();
+    let args = generics.params.iter().map(|p| match p {
+        _ if is_generic_setter(p) => quote!(::derive_builder::Unset),
+        GenericParam::Type(t) => t.ident.to_token_stream(),
+        GenericParam::Lifetime(l) => l.lifetime.to_token_stream(),
+        GenericParam::Const(c) => c.ident.to_token_stream(),
+    });
+    let ty_generics = if generics.params.is_empty() {
+        quote!()
+    } else {
+        quote!(<#( #args ),*>)
+    };
+    let (impl_generics, _, where_clause) = impl_generics.split_for_impl();
+
+    let params = positional.iter().map(|f| {
+        let FieldIr {
+            name, cfgs, span, ..
+        } = f;
+        let ty = to_setter_ty(f);
+        quote_spanned!(*span=> #( #cfgs )* #name: #ty)
+    });
+    let init = to_builder_init(ir, positional);
+    let constness = to_constness(ir);
+
+    quote! {
+        impl #impl_generics #target #ty_generics #where_clause {
+            pub #constness fn builder(#( #params ),*) -> #builder #ty_generics {
+                #init
+            }
+        }
+    }
+}
+
+/// Implements the runtime `Builder` and `Buildable` traits so that generic
+/// code can get at the builder of any derived type.
+fn to_trait_impls(ir: &Ir, positional: &[&FieldIr]) -> TokenStream {
+    let Ir {
+        target,
+        builder,
+        generics,
+        required_in_constructor,
+        ..
+    } = ir;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // A builder created without the constructor's arguments would be
+    // missing required fields that the infallible `build()` relies on. Once
+    // a previous `build()` has taken them, the inherent `build()` panics, so
+    // the trait reports them as missing instead.
+    if *required_in_constructor {
+        let taken_checks = positional.iter().map(|f| {
+            let FieldIr {
+                name, cfgs, span, ..
+            } = f;
+            let field = name.unraw().to_string();
+            let is_unset = to_slot_is_unset(f, &quote!(self));
+            quote_spanned! {*span=>
+                #( #cfgs )*
+                if #is_unset {
+                    return ::core::result::Result::Err(::derive_builder::BuildError::MissingField {
+                        field: #field,
+                    });
+                }
+            }
+        });
+        return quote! {
+            impl #impl_generics ::derive_builder::Builder for #builder #ty_generics #where_clause {
+                type Target = #target #ty_generics;
+                type Error = ::derive_builder::BuildError;
+
+                fn build(&mut self) -> ::core::result::Result<Self::Target, Self::Error> {
+                    #( #taken_checks )*
+                    ::core::result::Result::Ok(#builder::build(self))
+                }
+            }
+        };
+    }
+
+    let empty = to_builder_init(ir, &[]);
+
+    quote! {
+        impl #impl_generics ::core::default::Default for #builder #ty_generics #where_clause {
+            fn default() -> Self {
+                #empty
+            }
+        }
+
+        impl #impl_generics ::derive_builder::Builder for #builder #ty_generics #where_clause {
+            type Target = #target #ty_generics;
+            type Error = ::derive_builder::BuildError;
+
+            fn build(&mut self) -> ::core::result::Result<Self::Target, Self::Error> {
+                #builder::build(self)
+            }
+        }
+
+        impl #impl_generics ::derive_builder::Buildable for #target #ty_generics #where_clause {
+            type Builder = #builder #ty_generics;
+            type Error = ::derive_builder::BuildError;
+
+            fn builder() -> Self::Builder {
+                ::core::default::Default::default()
+            }
+        }
+    }
+}
+
+/// An owned setter for a field whose type is a bare type parameter, which
+/// returns a builder with that parameter replaced by the argument's type.
+fn to_generic_setter(ir: &Ir, f: &FieldIr) -> Option<TokenStream> {
+    let Ir {
+        builder,
+        generics,
+        fields,
+        ..
+    } = ir;
+    let FieldIr {
+        name,
+        cfgs,
+        setter_attrs,
+        span,
+        ..
+    } = f;
+    let param = f.generic_setter.as_ref()?;
+    let new_param = format_ident!("__{}", param);
+
+    let args = generics.params.iter().map(|p| match p {
+        GenericParam::Type(t) if t.ident == *param => new_param.to_token_stream(),
+        GenericParam::Type(t) => t.ident.to_token_stream(),
+        GenericParam::Lifetime(l) => l.lifetime.to_token_stream(),
+        GenericParam::Const(c) => c.ident.to_token_stream(),
+    });
+    let value = to_field_value(f, quote!(#name));
+    let others = fields.iter().filter(|o| o.name != *name).map(|o| {
+        let FieldIr { name, cfgs, .. } = o;
+        quote!(#( #cfgs )* #name: self.#name,)
+    });
+    let set_twice = if fields.iter().any(|f| f.set_once.is_some()) {
+        quote!(__set_twice: self.__set_twice,)
+    } else {
+        quote!()
+    };
+    // The guard records into `self` before it is rebuilt with the new type.
+    let guard = to_set_once_guard(f);
+    let recv = if f.set_once.is_some() {
+        quote!(mut self)
+    } else {
+        quote!(self)
+    };
+
+    Some(quote_spanned! {*span=>
+        #( #cfgs )*
+        #( #setter_attrs )*
+        pub fn #name<#new_param>(#recv, #name: #new_param) -> #builder<#( #args ),*> {
+            #guard
+            #builder {
+                #name: ::core::option::Option::Some(#value),
+                #( #others )*
+                #set_twice
+            }
+        }
+    })
+}
+
+fn to_builder_field(f: &FieldIr) -> TokenStream {
+    let FieldIr {
+        field_ty,
+        name,
+        cfgs,
+        field_attrs,
+        span,
+        ..
+    } = f;
+    // Every slot holds a complete value of the field, so an optional field
+    // explicitly set to `None` is distinguishable from one never touched.
+    quote_spanned! {*span=>
+        #( #cfgs )*
+        #( #field_attrs )*
+        #name: ::core::option::Option<#field_ty>
+    }
+}
+
+/// The type a field's setter takes.
+fn to_setter_ty(f: &FieldIr) -> TokenStream {
+    let FieldIr { ty, wrapper, .. } = f;
+    match wrapper {
+        Some(Wrapper::Pointer { inner, .. }) => inner.to_token_stream(),
+        Some(Wrapper::Cow) => quote!(impl ::core::convert::Into<#ty>),
+        None => ty.to_token_stream(),
+    }
+}
+
+/// The type a field's value is parsed as from a string, for `from_env()`
+/// and `set_by_name()`.
+fn to_parse_ty(f: &FieldIr) -> TokenStream {
+    let FieldIr { ty, wrapper, .. } = f;
+    match wrapper {
+        Some(Wrapper::Pointer { inner, .. }) => inner.to_token_stream(),
+        Some(Wrapper::Cow) => quote!(::std::string::String),
+        None => ty.to_token_stream(),
+    }
+}
+
+/// Wraps a value of the setter's argument type into a value of the field.
+fn to_field_value(f: &FieldIr, value: TokenStream) -> TokenStream {
+    let value = match &f.wrapper {
+        Some(Wrapper::Pointer { new, .. }) => quote_spanned!(f.span=> #new(#value)),
+        Some(Wrapper::Cow) => quote_spanned!(f.span=> ::core::convert::Into::into(#value)),
+        None => value,
+    };
+    if f.optional {
+        quote_spanned!(f.span=> ::core::option::Option::Some(#value))
+    } else {
+        value
+    }
+}
+
+/// A builder with nothing set except the `positional` fields, which are
+/// taken from variables of the same name.
+fn to_builder_init(ir: &Ir, positional: &[&FieldIr]) -> TokenStream {
+    let Ir {
+        builder, fields, ..
+    } = ir;
+    let is_positional = |f: &FieldIr| positional.iter().any(|p| p.name == f.name);
+    let inits = fields.iter().filter(|f| f.slot == Slot::Option).map(|f| {
+        let FieldIr {
+            name, cfgs, span, ..
+        } = f;
+        let init = if is_positional(f) {
+            let value = to_field_value(f, quote!(#name));
+            quote_spanned!(*span=> ::core::option::Option::Some(#value))
+        } else {
+            quote_spanned!(*span=> ::core::option::Option::None)
+        };
+        quote_spanned!(*span=> #( #cfgs )* #name: #init)
+    });
+    let slots = match ir.compact {
+        Some(_) => quote!(__slots: ::derive_builder::compact::Compact::new(),),
+        None => quote!(),
+    };
+    let set_twice = if fields.iter().any(|f| f.set_once.is_some()) {
+        quote!(__set_twice: ::std::vec::Vec::new())
+    } else {
+        quote!()
+    };
+    let init = quote! {
+        #builder {
+            #( #inits, )*
+            #slots
+            #set_twice
+        }
+    };
+
+    // Positional fields of a compact builder can only be stored once it
+    // exists.
+    let stores: Vec<_> = fields
+        .iter()
+        .filter(|f| f.slot != Slot::Option && is_positional(f))
+        .map(|f| {
+            let FieldIr { name, cfgs, .. } = f;
+            let value = to_field_value(f, quote!(#name));
+            let store = to_slot_store(f, &quote!(__builder), value);
+            quote!(#( #cfgs )* #store)
+        })
+        .collect();
+    if stores.is_empty() {
+        return init;
+    }
+    quote! {
+        let mut __builder = #init;
+        #( #stores )*
+        __builder
+    }
+}
+
+/// Guards an assignment to a `set_once` field against overwriting a value
+/// that was already set.
+fn to_set_once_guard(f: &FieldIr) -> TokenStream {
+    let FieldIr {
+        name,
+        span,
+        set_once,
+        ..
+    } = f;
+    let field = name.unraw().to_string();
+    let is_set = to_slot_is_set(f, &quote!(self));
+    let panic = match set_once {
+        None => return quote!(),
+        Some(SetOnce::Report) => quote!(),
+        Some(SetOnce::DebugPanic) => {
+            let msg = format!("field '{}' was set more than once", field);
+            quote_spanned! {*span=>
+                if ::core::cfg!(debug_assertions) {
+                    ::core::panic!(#msg);
+                }
+            }
+        }
+    };
+    quote_spanned! {*span=>
+        if #is_set {
+            #panic
+            self.__set_twice.push(#field);
+        }
+    }
+}
+
+fn to_setter(f: &FieldIr) -> Vec<TokenStream> {
+    let FieldIr {
+        name,
+        ty,
+        span,
+        each,
+        cfgs,
+        setter_attrs,
+        ..
+    } = f;
+
+    let mut items = vec![];
+    let slot = to_slot_get_mut_or_default(f, &quote!(self));
+
+    if let Some(each) = each {
+        let each = format_ident!("{}", each);
+        let each_item = quote_spanned! {*span=>
+            #( #cfgs )*
+            #( #setter_attrs )*
+            pub fn #each(&mut self, #each: #ty) -> &mut Self {
+                #slot.push(#each);
+                self
+            }
+        };
+
+        items.push(each_item);
+    }
+
+    if each.is_none() {
+        let ty = to_setter_ty(f);
+        let store = to_slot_store(f, &quote!(self), to_field_value(f, quote!(#name)));
+        let guard = to_set_once_guard(f);
+        let default_item = quote_spanned! {*span=>
+            #( #cfgs )*
+            #( #setter_attrs )*
+            pub fn #name(&mut self, #name: #ty) -> &mut Self {
+                #guard
+                #store
+                self
+            }
+        };
+        items.push(default_item);
+    } else {
+        if each != &Some(name.to_string()) {
+            let store = to_slot_store(
+                f,
+                &quote!(self),
+                quote!(::core::iter::FromIterator::from_iter(#name)),
+            );
+            let default_item = quote_spanned! {*span=>
+                #( #cfgs )*
+                #( #setter_attrs )*
+                pub fn #name<__I>(&mut self, #name: __I) -> &mut Self
+                where
+                    __I: ::core::iter::IntoIterator<Item = #ty>,
+                {
+                    #store
+                    self
+                }
+            };
+            items.push(default_item);
+        }
+
+        let extend = format_ident!("extend_{}", name);
+        let clear = format_ident!("clear_{}", name);
+        items.push(quote_spanned! {*span=>
+            #( #cfgs )*
+            #( #setter_attrs )*
+            pub fn #extend<__I>(&mut self, items: __I) -> &mut Self
+            where
+                __I: ::core::iter::IntoIterator<Item = #ty>,
+            {
+                ::core::iter::Extend::extend(#slot, items);
+                self
+            }
+        });
+        // An explicitly empty collection rather than an unset one, so that a
+        // patch that clears a field empties it in the target.
+        let store = to_slot_store(
+            f,
+            &quote!(self),
+            quote!(::core::default::Default::default()),
+        );
+        items.push(quote_spanned! {*span=>
+            #( #cfgs )*
+            #( #setter_attrs )*
+            pub fn #clear(&mut self) -> &mut Self {
+                #store
+                self
+            }
+        });
+    }
+    items
+}
+
+/// `const` for the functions of a `const` builder, unless errors ruled it out.
+fn to_constness(ir: &Ir) -> TokenStream {
+    if ir.const_builder && ir.const_fns {
+        quote!(const)
+    } else {
+        quote!()
+    }
+}
+
+/// A setter of a `const` builder. It takes and returns the builder by value,
+/// so that a chain starting at `Target::builder()` can end in
+/// `build_const()` inside a constant.
+fn to_const_setter(ir: &Ir, f: &FieldIr) -> TokenStream {
+    let FieldIr {
+        name,
+        ty,
+        span,
+        cfgs,
+        setter_attrs,
+        ..
+    } = f;
+    let store = to_slot_store(f, &quote!(self), to_field_value(f, quote!(#name)));
+    let constness = to_constness(ir);
+    quote_spanned! {*span=>
+        #( #cfgs )*
+        #( #setter_attrs )*
+        pub #constness fn #name(mut self, #name: #ty) -> Self {
+            #store
+            self
+        }
+    }
+}
+
+/// The `#[deprecated]` setters for the field's old names, which forward to
+/// the field's main setter with the same signature.
+fn to_alias_setters(ir: &Ir, f: &FieldIr) -> Vec<TokenStream> {
+    let FieldIr {
+        name,
+        ty,
+        each,
+        cfgs,
+        deprecated_aliases,
+        deprecation_note,
+        ..
+    } = f;
+
+    let note = match deprecation_note {
+        Some(note) => note.value(),
+        None => format!("renamed to `{}`", name),
+    };
+    let doc = format!(" Deprecated alias of [`{}`](Self::{}).", name, name);
+    let (generics, params, where_clause) = match each {
+        None => {
+            let ty = to_setter_ty(f);
+            (quote!(), quote!(#name: #ty), quote!())
+        }
+        Some(each) if name == each => (quote!(), quote!(#name: #ty), quote!()),
+        Some(_) => (
+            quote!(<__I>),
+            quote!(#name: __I),
+            quote!(where __I: ::core::iter::IntoIterator<Item = #ty>),
+        ),
+    };
+    let (constness, receiver, ret) = if ir.const_builder {
+        (to_constness(ir), quote!(self), quote!(Self))
+    } else {
+        (quote!(), quote!(&mut self), quote!(&mut Self))
+    };
+
+    deprecated_aliases
+        .iter()
+        .map(|alias| {
+            quote_spanned! {alias.span()=>
+                #( #cfgs )*
+                #[doc = #doc]
+                #[deprecated(note = #note)]
+                pub #constness fn #alias #generics(#receiver, #params) -> #ret #where_clause {
+                    self.#name(#name)
+                }
+            }
+        })
+        .collect()
+}
+
+fn to_field_info(f: &FieldIr) -> TokenStream {
+    let FieldIr {
+        name,
+        field_ty,
+        required,
+        each,
+        doc,
+        cfgs,
+        span,
+        ..
+    } = f;
+    let name = name.unraw().to_string();
+    let ty = type_to_string(field_ty);
+    let each = match each {
+        Some(each) => quote!(::core::option::Option::Some(#each)),
+        None => quote!(::core::option::Option::None),
+    };
+    let default = if let Some(DefaultWith { lit, .. }) = &f.default_with {
+        quote!(::core::option::Option::Some(#lit))
+    } else if *required {
+        quote!(::core::option::Option::None)
+    } else if f.each.is_some() {
+        quote!(::core::option::Option::Some("Default::default()"))
+    } else {
+        quote!(::core::option::Option::Some("None"))
+    };
+    quote_spanned! {*span=>
+        #( #cfgs )*
+        ::derive_builder::FieldInfo {
+            name: #name,
+            ty: #ty,
+            required: #required,
+            each: #each,
+            doc: #doc,
+            default: #default,
+        }
+    }
+}
+
+/// Renders a type the way it was written, without the spaces that
+/// `TokenStream`'s `Display` puts between every token.
+fn type_to_string(ty: &Type) -> String {
+    let mut s = quote!(#ty).to_string();
+    for (from, to) in [
+        (" <", "<"),
+        ("< ", "<"),
+        (" >", ">"),
+        (" ,", ","),
+        (" :: ", "::"),
+        (":: ", "::"),
+        ("& ", "&"),
+        (" ;", ";"),
+        ("[ ", "["),
+        (" ]", "]"),
+        ("( ", "("),
+        (" )", ")"),
+    ] {
+        s = s.replace(from, to);
+    }
+    s
+}
+
+fn to_env_fill(f: &FieldIr) -> Option<TokenStream> {
+    let FieldIr {
+        env, cfgs, span, ..
+    } = f;
+    let var = env.as_ref()?;
+    let ty = to_parse_ty(f);
+    let is_unset = to_slot_is_unset(f, &quote!(self));
+    let store = to_slot_store(f, &quote!(self), to_field_value(f, quote!(value)));
+    Some(quote_spanned! {*span=>
+        #( #cfgs )*
+        if #is_unset {
+            if let ::core::option::Option::Some(value) = ::derive_builder::__parse_env::<#ty>(#var)? {
+                #store
+            }
+        }
+    })
+}
+
+fn to_set_by_name_arm(f: &FieldIr) -> TokenStream {
+    let FieldIr {
+        name,
+        each,
+        keys,
+        cfgs,
+        span,
+        ..
+    } = f;
+    // `r#type` is set as `type`.
+    let field = name.unraw().to_string();
+    let ty = to_parse_ty(f);
+    let parsed = quote_spanned! {*span=>
+        ::derive_builder::__parse_value::<#ty>(#field, value)?
+    };
+    let assign = if each.is_some() {
+        let slot = to_slot_get_mut_or_default(f, &quote!(self));
+        quote_spanned! {*span=>
+            #slot.push(#parsed)
+        }
+    } else {
+        let store = to_slot_store(f, &quote!(self), to_field_value(f, parsed));
+        let guard = to_set_once_guard(f);
+        quote_spanned! {*span=>
+            {
+                #guard
+                #store
+            }
+        }
+    };
+    quote_spanned! {*span=>
+        #( #cfgs )*
+        #field #( | #keys )* => #assign,
+    }
+}
+
+fn to_constraint_checks(f: &FieldIr, fields: &[FieldIr]) -> Vec<TokenStream> {
+    let FieldIr {
+        name,
+        cfgs,
+        span,
+        requires,
+        conflicts_with,
+        ..
+    } = f;
+    let field = name.unraw().to_string();
+    let is_set = to_slot_is_set(f, &quote!(self));
+
+    let mut checks = vec![];
+
+    for other in requires {
+        let requires = other.unraw().to_string();
+        let other = field_named(fields, other);
+        let other_cfgs = &other.cfgs;
+        let other_is_unset = to_slot_is_unset(other, &quote!(self));
+        checks.push(quote_spanned! {*span=>
+            #( #cfgs )*
+            #( #other_cfgs )*
+            if #is_set && #other_is_unset {
+                return ::core::result::Result::Err(::derive_builder::BuildError::Requires {
+                    field: #field,
+                    requires: #requires,
+                });
+            }
+        });
+    }
+
+    for other in conflicts_with {
+        let conflicts_with = other.unraw().to_string();
+        let other = field_named(fields, other);
+        let other_cfgs = &other.cfgs;
+        let other_is_set = to_slot_is_set(other, &quote!(self));
+        checks.push(quote_spanned! {*span=>
+            #( #cfgs )*
+            #( #other_cfgs )*
+            if #is_set && #other_is_set {
+                return ::core::result::Result::Err(::derive_builder::BuildError::ConflictsWith {
+                    field: #field,
+                    conflicts_with: #conflicts_with,
+                });
+            }
+        });
+    }
+    checks
+}
+
+fn to_group_check(g: &GroupIr, fields: &[FieldIr]) -> TokenStream {
+    let GroupIr { name, exactly_one } = g;
+    let members: Vec<_> = exactly_one.iter().map(|f| field_named(fields, f)).collect();
+    let names = members.iter().map(|f| {
+        let cfgs = &f.cfgs;
+        let name = f.name.unraw().to_string();
+        quote!(#( #cfgs )* #name)
+    });
+    let is_set = members.iter().map(|f| {
+        let cfgs = &f.cfgs;
+        let is_set = to_slot_is_set(f, &quote!(self));
+        quote!(#( #cfgs )* #is_set)
+    });
+
+    quote! {
+        {
+            const FIELDS: &[&str] = &[#( #names ),*];
+            let set: ::std::vec::Vec<&'static str> = [#( #is_set ),*]
+                .iter()
+                .zip(FIELDS)
+                .filter(|(is_set, _)| **is_set)
+                .map(|(_, field)| *field)
+                .collect();
+            if set.len() != 1 {
+                return ::core::result::Result::Err(::derive_builder::BuildError::ExactlyOne {
+                    group: #name,
+                    fields: FIELDS,
+                    set,
+                });
+            }
+        }
+    }
+}
+
+fn to_build_binding(f: &FieldIr, positional: &[&FieldIr]) -> TokenStream {
+    let FieldIr {
+        name,
+        cfgs,
+        span,
+        required,
+        each,
+        default_with,
+        ..
+    } = f;
+    let take = to_slot_take(f, &quote!(self));
+
+    if positional.iter().any(|p| p.name == *name) {
+        let msg = format!(
+            "field '{}' was already taken by a previous build()",
+            name.unraw()
+        );
+        quote_spanned! {*span=>
+            #( #cfgs )*
+            let #name = #take.expect(#msg);
+        }
+    } else if let Some(DefaultWith { func, deps, .. }) = default_with {
+        quote_spanned! {*span=>
+            #( #cfgs )*
+            let #name = match #take {
+                ::core::option::Option::Some(value) => value,
+                ::core::option::Option::None => (#func)(#( &#deps ),*),
+            };
+        }
+    } else if *required {
+        let field = name.unraw().to_string();
+        quote_spanned! {*span=>
+            #( #cfgs )*
+            let #name = #take.ok_or(::derive_builder::BuildError::MissingField {
+                field: #field,
+            })?;
+        }
+    } else if each.is_some() {
+        quote_spanned! {*span=>
+            #( #cfgs )*
+            let #name = #take.unwrap_or_default();
+        }
+    } else {
+        quote_spanned! {*span=>
+            #( #cfgs )*
+            let #name = #take.flatten();
+        }
+    }
+}
+
+// Every read and write of a builder slot goes through the helpers below, so
+// that the rest of codegen doesn't care whether the builder is `compact`.
+// `recv` is the builder expression, usually `self`.
+
+/// A `bool` expression: whether the field has been set.
+fn to_slot_is_set(f: &FieldIr, recv: &TokenStream) -> TokenStream {
+    let name = &f.name;
+    match f.slot {
+        Slot::Option => quote_spanned!(f.span=> #recv.#name.is_some()),
+        Slot::Compact { index } => quote_spanned!(f.span=> #recv.__slots.is_set::<#index>()),
+    }
+}
+
+/// The negation of `to_slot_is_set()`.
+fn to_slot_is_unset(f: &FieldIr, recv: &TokenStream) -> TokenStream {
+    let name = &f.name;
+    match f.slot {
+        Slot::Option => quote_spanned!(f.span=> #recv.#name.is_none()),
+        Slot::Compact { index } => quote_spanned!(f.span=> !#recv.__slots.is_set::<#index>()),
+    }
+}
+
+/// An `Option<&FieldTy>` expression.
+fn to_slot_get(f: &FieldIr, recv: &TokenStream) -> TokenStream {
+    let name = &f.name;
+    match f.slot {
+        Slot::Option => quote_spanned!(f.span=> #recv.#name.as_ref()),
+        Slot::Compact { index } => quote_spanned!(f.span=> #recv.__slots.get::<#index>()),
+    }
+}
+
+/// A statement storing `value` in the field, dropping any previous value.
+fn to_slot_store(f: &FieldIr, recv: &TokenStream, value: TokenStream) -> TokenStream {
+    let name = &f.name;
+    match f.slot {
+        Slot::Option => quote_spanned! {f.span=>
+            #recv.#name = ::core::option::Option::Some(#value);
+        },
+        Slot::Compact { index } => quote_spanned! {f.span=>
+            #recv.__slots.set::<#index>(#value);
+        },
+    }
+}
+
+/// A `&mut FieldTy` expression, storing the default value first if the field
+/// isn't set.
+fn to_slot_get_mut_or_default(f: &FieldIr, recv: &TokenStream) -> TokenStream {
+    let name = &f.name;
+    match f.slot {
+        Slot::Option => quote_spanned! {f.span=>
+            #recv.#name.get_or_insert_with(::core::default::Default::default)
+        },
+        Slot::Compact { index } => quote_spanned! {f.span=>
+            #recv.__slots.get_mut_or_insert_with::<#index>(::core::default::Default::default)
+        },
+    }
+}
+
+/// An `Option<FieldTy>` expression moving the value out of the field and
+/// leaving it unset.
+fn to_slot_take(f: &FieldIr, recv: &TokenStream) -> TokenStream {
+    let name = &f.name;
+    match f.slot {
+        Slot::Option => quote_spanned!(f.span=> #recv.#name.take()),
+        Slot::Compact { index } => quote_spanned!(f.span=> #recv.__slots.take::<#index>()),
+    }
+}
+
+/// The field named `name`, which `lower` has already checked exists.
+fn field_named<'a>(fields: &'a [FieldIr], name: &Ident) -> &'a FieldIr {
+    fields
+        .iter()
+        .find(|f| f.name == *name)
+        .expect("field names are resolved by lower")
+}
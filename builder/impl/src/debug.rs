@@ -0,0 +1,123 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use proc_macro_error::emit_call_site_error;
+use quote::format_ident;
+
+use crate::analyze::Model;
+use crate::codegen::Rust;
+use crate::lower::{mentions, Ir};
+
+/// Set to `1` to dump every derived builder, or to a comma-separated list of
+/// target type names to dump only those.
+const ENV_VAR: &str = "DERIVE_BUILDER_DEBUG";
+
+/// Whether the expansion for `model` should be dumped, either because of
+/// `#[builder(debug_expansion)]` or the environment variable.
+///
+/// Cargo doesn't track environment variables read by a proc macro, so
+/// changing `DERIVE_BUILDER_DEBUG` alone doesn't re-expand crates that are
+/// already compiled.
+pub fn requested(model: &Model) -> bool {
+    let attr = model.attrs.iter().any(|a| {
+        a.path.is_ident("builder") && mentions(a.tokens.clone(), &format_ident!("debug_expansion"))
+    });
+    let var = env::var(ENV_VAR).unwrap_or_default();
+    let target = model.target.to_string();
+    attr || var == "1" || var.split(',').any(|name| name.trim() == target)
+}
+
+/// The intermediate stages of one expansion, captured as they go by.
+pub struct Dump {
+    pub target: String,
+    pub model: String,
+    pub ir: String,
+}
+
+impl Dump {
+    pub fn model(model: &Model) -> Self {
+        Dump {
+            target: model.target.to_string(),
+            model: format!("{:#?}", model),
+            ir: String::new(),
+        }
+    }
+
+    pub fn ir(&mut self, ir: &Ir) {
+        self.ir = format!("{:#?}", ir);
+    }
+
+    /// Writes `<Target>.expanded.rs` and `<Target>.debug.txt` into
+    /// `derive_builder/` under the dump directory.
+    pub fn write(&self, expanded: &Rust) {
+        let dir = dump_dir().join("derive_builder");
+        if let Err(err) = self.write_to(&dir, &expanded.to_string()) {
+            emit_call_site_error!(
+                "couldn't write the expansion of `{}` to {}: {}",
+                self.target,
+                dir.display(),
+                err
+            );
+        }
+    }
+
+    fn write_to(&self, dir: &Path, expanded: &str) -> io::Result<()> {
+        // Errors may have left the tokens unparseable; dump them raw then.
+        let code = match syn::parse_file(expanded) {
+            Ok(file) => prettyplease::unparse(&file),
+            Err(_) => expanded.to_owned(),
+        };
+        let debug = format!("// Model\n{}\n\n// Ir\n{}\n", self.model, self.ir);
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(format!("{}.expanded.rs", self.target)), code)?;
+        fs::write(dir.join(format!("{}.debug.txt", self.target)), debug)
+    }
+}
+
+/// `OUT_DIR` when the crate has a build script, otherwise the target
+/// directory: `CARGO_TARGET_DIR`, or the nearest `target/` above the crate.
+fn dump_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("OUT_DIR").or_else(|| env::var_os("CARGO_TARGET_DIR")) {
+        return dir.into();
+    }
+    let manifest_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default());
+    manifest_dir
+        .ancestors()
+        .map(|dir| dir.join("target"))
+        .find(|dir| dir.is_dir())
+        .unwrap_or_else(|| manifest_dir.join("target"))
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs;
+
+    use super::Dump;
+
+    #[test]
+    fn test_write_to() {
+        let dir = env::temp_dir().join(format!("derive_builder_dump_{}", std::process::id()));
+        let dump = Dump {
+            target: "Config".to_owned(),
+            model: "Model { .. }".to_owned(),
+            ir: "Ir { .. }".to_owned(),
+        };
+
+        dump.write_to(&dir, "struct Config { host : String , }")
+            .unwrap();
+        let expanded = fs::read_to_string(dir.join("Config.expanded.rs")).unwrap();
+        assert_eq!(expanded, "struct Config {\n    host: String,\n}\n");
+        let debug = fs::read_to_string(dir.join("Config.debug.txt")).unwrap();
+        assert_eq!(debug, "// Model\nModel { .. }\n\n// Ir\nIr { .. }\n");
+
+        // Tokens that don't parse are written as they are.
+        dump.write_to(&dir, "struct Config {").unwrap();
+        let expanded = fs::read_to_string(dir.join("Config.expanded.rs")).unwrap();
+        assert_eq!(expanded, "struct Config {");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,35 @@
+use proc_macro_error::{proc_macro_error, set_dummy};
+use syn::{parse_macro_input, DeriveInput};
+
+use crate::{analyze::analyze, codegen::codegen, debug::Dump, lower::lower};
+
+mod analyze;
+mod codegen;
+mod debug;
+mod lower;
+
+pub(crate) type Ast = DeriveInput;
+
+#[proc_macro_derive(Builder, attributes(builder))]
+#[proc_macro_error]
+pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput) as Ast;
+    let model = match analyze(ast) {
+        Some(model) => model,
+        None => return proc_macro::TokenStream::new(),
+    };
+    let mut dump = debug::requested(&model).then(|| Dump::model(&model));
+    let ir = lower(model);
+    if let Some(dump) = &mut dump {
+        dump.ir(&ir);
+    }
+    let expanded = codegen(ir);
+    if let Some(dump) = &dump {
+        dump.write(&expanded);
+    }
+    // Every stage reports errors and carries on without whatever was wrong,
+    // so the builder is emitted alongside the errors. That keeps code using
+    // it free of follow-up errors and IDE completions working meanwhile.
+    set_dummy(expanded.clone().into());
+    expanded
+}
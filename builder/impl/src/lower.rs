@@ -0,0 +1,1128 @@
+use std::fmt;
+use std::fmt::Display;
+
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use proc_macro_error::emit_error;
+use quote::{quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    bracketed, parenthesized, token, Attribute, Expr, Field, GenericArgument, Generics, Lit,
+    LitStr, Meta, MetaNameValue, NestedMeta, Pat, PatType, Path, PathArguments, PathSegment, Token,
+    Type, Visibility,
+};
+
+use crate::analyze::Model;
+
+struct Symbol(&'static str);
+
+const BUILDER: Symbol = Symbol("builder");
+const DOC: Symbol = Symbol("doc");
+const CFG: Symbol = Symbol("cfg");
+const FIELD_ATTR: Symbol = Symbol("field_attr");
+const SETTER_ATTR: Symbol = Symbol("setter_attr");
+const EACH: Symbol = Symbol("each");
+const REQUIRES: Symbol = Symbol("requires");
+const CONFLICTS_WITH: Symbol = Symbol("conflicts_with");
+const GROUP: Symbol = Symbol("group");
+const NAME: Symbol = Symbol("name");
+const EXACTLY_ONE: Symbol = Symbol("exactly_one");
+const ENV: Symbol = Symbol("env");
+const ENV_PREFIX: Symbol = Symbol("env_prefix");
+const SET_BY_NAME: Symbol = Symbol("set_by_name");
+const KEY: Symbol = Symbol("key");
+const SKIP_BY_NAME: Symbol = Symbol("skip_by_name");
+const DEFAULT_WITH: Symbol = Symbol("default_with");
+const SET_ONCE: Symbol = Symbol("set_once");
+const GENERIC_SETTER: Symbol = Symbol("generic_setter");
+const SETTER: Symbol = Symbol("setter");
+const DEPRECATED_ALIAS: Symbol = Symbol("deprecated_alias");
+const NOTE: Symbol = Symbol("note");
+const SINCE: Symbol = Symbol("since");
+const RAW: Symbol = Symbol("raw");
+const PATCH: Symbol = Symbol("patch");
+const COMPACT: Symbol = Symbol("compact");
+const DEBUG_EXPANSION: Symbol = Symbol("debug_expansion");
+const REQUIRED_IN_CONSTRUCTOR: Symbol = Symbol("required_in_constructor");
+
+impl PartialEq<Symbol> for Ident {
+    fn eq(&self, word: &Symbol) -> bool {
+        self == word.0
+    }
+}
+
+impl PartialEq<Symbol> for &Ident {
+    fn eq(&self, word: &Symbol) -> bool {
+        *self == word.0
+    }
+}
+
+impl PartialEq<Symbol> for Path {
+    fn eq(&self, word: &Symbol) -> bool {
+        self.is_ident(word.0)
+    }
+}
+
+impl PartialEq<Symbol> for &Path {
+    fn eq(&self, word: &Symbol) -> bool {
+        self.is_ident(word.0)
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Ir {
+    pub target: Ident,
+    pub builder: Ident,
+    /// The target's visibility, which the builder shares.
+    pub vis: Visibility,
+    pub generics: Generics,
+    pub fields: Vec<FieldIr>,
+    pub groups: Vec<GroupIr>,
+    /// Whether to generate the string-keyed `set_by_name()` and `from_pairs()`.
+    pub set_by_name: bool,
+    /// Whether `Target::builder(...)` takes the required fields positionally.
+    pub required_in_constructor: bool,
+    /// Whether to generate `apply_to()`, `append_to()` and `diff()`.
+    pub patch: bool,
+    /// Set when fields are stored in a `derive_builder::compact::Compact`
+    /// instead of in `Option`s: the width of its mask, the smallest that
+    /// fits every field.
+    pub compact: Option<usize>,
+    /// Whether the setters take and return the builder by value, and
+    /// `build_const()` is generated.
+    pub const_builder: bool,
+    /// Whether those are `const fn`s, which they aren't when the struct's
+    /// fields rule it out, so that call sites only see the one error.
+    pub const_fns: bool,
+    /// Indices into `fields` in the order `build()` resolves them, so that
+    /// every `default_with` runs after the fields it reads.
+    pub resolve_order: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldIr {
+    pub name: Ident,
+    pub ty: Type,
+    pub field_ty: Type,
+    /// Whether the field is an `Option` and its setter takes the inner type.
+    pub optional: bool,
+    pub required: bool,
+    pub each: Option<String>,
+    pub requires: Vec<Ident>,
+    pub conflicts_with: Vec<Ident>,
+    /// The environment variable the field is filled from, prefix included.
+    pub env: Option<String>,
+    /// Extra names `set_by_name()` accepts for the field besides its own.
+    pub keys: Vec<String>,
+    /// Left out of `set_by_name()`, for types that can't be parsed from a
+    /// string.
+    pub skip_by_name: bool,
+    /// The field's doc comment, one line per `#[doc]` attribute.
+    pub doc: String,
+    pub default_with: Option<DefaultWith>,
+    pub set_once: Option<SetOnce>,
+    /// The type parameter replaced by `#[builder(generic_setter)]`.
+    pub generic_setter: Option<Ident>,
+    pub slot: Slot,
+    /// Old names of the field's setter, kept as `#[deprecated]` setters that
+    /// forward to the current one.
+    pub deprecated_aliases: Vec<Ident>,
+    /// The `#[deprecated]` note of those aliases.
+    pub deprecation_note: Option<LitStr>,
+    /// Set when the setter takes something more convenient than `ty` and
+    /// wraps it, unless the field is marked `#[builder(setter(raw))]`.
+    pub wrapper: Option<Wrapper>,
+    /// The field's `#[cfg]` attributes, repeated on everything generated for
+    /// it so that a disabled field disappears from the builder too. The
+    /// compiler evaluates `#[cfg]` and `#[cfg_attr]` before a derive runs, so
+    /// this is only a safeguard for input that wasn't expanded first.
+    pub cfgs: Vec<Attribute>,
+    /// Extra attributes for the builder's field, from `field_attr(...)`.
+    pub field_attrs: Vec<TokenStream>,
+    /// Extra attributes for the field's setters, from `setter_attr(...)`.
+    pub setter_attrs: Vec<TokenStream>,
+    pub span: Span,
+}
+
+/// How a `#[builder(set_once)]` field reacts to being set a second time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOnce {
+    /// `build()` fails with `BuildError::SetTwice`.
+    Report,
+    /// Panics right away in debug builds, and reports in release builds.
+    DebugPanic,
+}
+
+/// How the builder stores a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    /// An `Option` of the field's type.
+    Option,
+    /// Element `index` of the builder's `__slots`.
+    Compact { index: usize },
+}
+
+/// A field type whose setter accepts the value it wraps, recognized by
+/// `extract_wrapper`.
+#[derive(Debug, Clone)]
+pub enum Wrapper {
+    /// `Box<T>`, `Rc<T>` or `Arc<T>`: the setter takes `T` and calls `new`.
+    Pointer { new: TokenStream, inner: Box<Type> },
+    /// `Cow<'a, str>`: the setter takes anything `Into<Cow<'a, str>>`.
+    Cow,
+}
+
+/// A field-level `#[builder(default_with = "...")]`.
+#[derive(Debug, Clone)]
+pub struct DefaultWith {
+    /// The closure or function computing the field's value.
+    pub func: Expr,
+    /// The fields passed to `func` by reference, in order.
+    pub deps: Vec<Ident>,
+    pub lit: LitStr,
+}
+
+/// A struct-level `#[builder(group(name = "...", exactly_one = [...]))]`.
+#[derive(Debug, Clone)]
+pub struct GroupIr {
+    pub name: String,
+    pub exactly_one: Vec<Ident>,
+}
+
+pub fn lower(model: Model) -> Ir {
+    let names: Vec<Ident> = model
+        .fields
+        .iter()
+        .filter_map(|f| f.ident.clone())
+        .collect();
+
+    let mut groups = vec![];
+    let mut env_prefix = String::new();
+    let mut set_by_name = false;
+    let mut required_in_constructor = None;
+    let mut patch = false;
+    let mut compact = None;
+    let mut const_builder = None;
+    let mut struct_set_once = None;
+    for attr in model.attrs.iter().filter(|a| a.path == BUILDER) {
+        let args = match attr.parse_args_with(Punctuated::<StructArg, Token![,]>::parse_terminated)
+        {
+            Ok(args) => args,
+            Err(err) => {
+                emit_error!(err);
+                continue;
+            }
+        };
+        for arg in args {
+            match arg {
+                StructArg::Group(group) => groups.push(GroupIr {
+                    name: group.name.value(),
+                    exactly_one: group
+                        .exactly_one
+                        .iter()
+                        .filter_map(|lit| resolve_field(&names, lit))
+                        .collect(),
+                }),
+                // Parse `#[builder(env_prefix = "APP_")]`
+                StructArg::Meta(NestedMeta::Meta(Meta::NameValue(m))) if m.path == ENV_PREFIX => {
+                    if let Some(lit) = get_lit_str(&m) {
+                        env_prefix = lit.value();
+                    }
+                }
+                // Parse `#[builder(set_by_name)]`
+                StructArg::Meta(NestedMeta::Meta(Meta::Path(p))) if p == SET_BY_NAME => {
+                    set_by_name = true;
+                }
+                // Parse `#[builder(required_in_constructor)]`
+                StructArg::Meta(NestedMeta::Meta(Meta::Path(p)))
+                    if p == REQUIRED_IN_CONSTRUCTOR =>
+                {
+                    required_in_constructor = Some(p);
+                }
+                // Parse `#[builder(patch)]`
+                StructArg::Meta(NestedMeta::Meta(Meta::Path(p))) if p == PATCH => {
+                    patch = true;
+                }
+                // Parse `#[builder(compact)]`
+                StructArg::Meta(NestedMeta::Meta(Meta::Path(p))) if p == COMPACT => {
+                    if model.fields.len() > 64 {
+                        emit_error!(p, "`compact` supports at most 64 fields");
+                    } else {
+                        compact = Some(p);
+                    }
+                }
+                // Parse `#[builder(set_once)]` or `#[builder(set_once = "debug_panic")]`
+                StructArg::Meta(NestedMeta::Meta(meta)) if meta.path() == SET_ONCE => {
+                    struct_set_once = lower_set_once(&meta);
+                }
+                // `#[builder(debug_expansion)]` is handled by `debug::requested`.
+                StructArg::Meta(NestedMeta::Meta(Meta::Path(p))) if p == DEBUG_EXPANSION => {}
+                // Parse `#[builder(const)]`
+                StructArg::Const(token) => const_builder = Some(token),
+                StructArg::Meta(meta) => emit_error!(meta, "unknown builder attribute"),
+            }
+        }
+    }
+
+    let mut seen_keys: Vec<String> = names.iter().map(|n| n.unraw().to_string()).collect();
+    let mut fields = vec![];
+    let self_ty: Type = {
+        let target = &model.target;
+        let (_, ty_generics, _) = model.generics.split_for_impl();
+        syn::parse_quote!(#target #ty_generics)
+    };
+    let word_bits = match model.fields.len() {
+        0..=8 => 8,
+        9..=16 => 16,
+        17..=32 => 32,
+        _ => 64,
+    };
+    let compact = compact.map(|_| word_bits);
+    for (i, f) in model.fields.iter().enumerate() {
+        let mut each = None;
+        let mut requires = vec![];
+        let mut conflicts_with = vec![];
+        let mut env = None;
+        let mut keys = vec![];
+        let mut skip_by_name = None;
+        let mut default_with = None;
+        let mut set_once = None;
+        let mut generic_setter = None;
+        let mut raw = false;
+        let mut deprecated_aliases = vec![];
+        let mut deprecation_note = None;
+        let cfgs: Vec<_> = f.attrs.iter().filter(|a| a.path == CFG).cloned().collect();
+        let mut field_attrs = vec![];
+        let mut setter_attrs = vec![];
+
+        for attr in f.attrs.iter().filter(|a| a.path == BUILDER) {
+            let meta = match attr.parse_meta() {
+                Ok(Meta::List(meta)) => meta,
+                Ok(meta) => {
+                    emit_error!(meta, "expected `builder(...)`");
+                    continue;
+                }
+                Err(err) => {
+                    emit_error!(err);
+                    continue;
+                }
+            };
+            for meta in meta.nested.into_iter() {
+                match &meta {
+                    // Parse `#[builder(each = "foo")]`
+                    NestedMeta::Meta(Meta::NameValue(m)) if m.path == EACH => {
+                        if let Some(lit) = get_lit_str(m) {
+                            match lit.parse::<Ident>() {
+                                Ok(_) => each = Some(lit.value()),
+                                Err(_) => emit_error!(lit, "expected a setter name"),
+                            }
+                        }
+                    }
+                    // Parse `#[builder(requires = "other")]`
+                    NestedMeta::Meta(Meta::NameValue(m)) if m.path == REQUIRES => {
+                        if let Some(other) = get_lit_str(m).and_then(|l| resolve_field(&names, &l))
+                        {
+                            requires.push(other);
+                        }
+                    }
+                    // Parse `#[builder(conflicts_with = "other")]`
+                    NestedMeta::Meta(Meta::NameValue(m)) if m.path == CONFLICTS_WITH => {
+                        if let Some(other) = get_lit_str(m).and_then(|l| resolve_field(&names, &l))
+                        {
+                            conflicts_with.push(other);
+                        }
+                    }
+                    // Parse `#[builder(env = "PORT")]`
+                    NestedMeta::Meta(Meta::NameValue(m)) if m.path == ENV => {
+                        env = get_lit_str(m);
+                    }
+                    // Parse `#[builder(key = "alias")]`
+                    NestedMeta::Meta(Meta::NameValue(m)) if m.path == KEY => {
+                        if let Some(lit) = get_lit_str(m) {
+                            if !set_by_name {
+                                emit_error!(
+                                    lit,
+                                    "`key` requires `#[builder(set_by_name)]` on the struct"
+                                );
+                            } else if seen_keys.contains(&lit.value()) {
+                                emit_error!(lit, "duplicate key `{}`", lit.value());
+                            } else {
+                                seen_keys.push(lit.value());
+                                keys.push(lit.value());
+                            }
+                        }
+                    }
+                    // Parse `#[builder(skip_by_name)]`
+                    NestedMeta::Meta(Meta::Path(p)) if p == SKIP_BY_NAME => {
+                        if !set_by_name {
+                            emit_error!(
+                                p,
+                                "`skip_by_name` requires `#[builder(set_by_name)]` on the struct"
+                            );
+                        } else {
+                            skip_by_name = Some(p.clone());
+                        }
+                    }
+                    // Parse `#[builder(default_with = "|other| ...")]`
+                    NestedMeta::Meta(Meta::NameValue(m)) if m.path == DEFAULT_WITH => {
+                        default_with = get_lit_str(m).and_then(|l| lower_default_with(&names, l));
+                    }
+                    // Parse `#[builder(set_once)]` or `#[builder(set_once = "debug_panic")]`
+                    NestedMeta::Meta(m) if m.path() == SET_ONCE => {
+                        set_once = lower_set_once(m).map(|s| (s, m.clone()));
+                    }
+                    // Parse `#[builder(generic_setter)]`
+                    NestedMeta::Meta(Meta::Path(p)) if p == GENERIC_SETTER => {
+                        generic_setter = Some(p.clone());
+                    }
+                    // Parse `#[builder(setter(raw))]`
+                    NestedMeta::Meta(Meta::List(m)) if m.path == SETTER => {
+                        for nested in m.nested.iter() {
+                            match nested {
+                                NestedMeta::Meta(Meta::Path(p)) if p == RAW => raw = true,
+                                _ => emit_error!(nested, "expected `setter(raw)`"),
+                            }
+                        }
+                    }
+                    // Parse `#[builder(deprecated_alias = "old_name")]`
+                    NestedMeta::Meta(Meta::NameValue(m)) if m.path == DEPRECATED_ALIAS => {
+                        if let Some(lit) = get_lit_str(m) {
+                            match lit.parse::<Ident>() {
+                                Ok(alias) => deprecated_aliases.push(alias),
+                                Err(_) => emit_error!(lit, "expected a setter name"),
+                            }
+                        }
+                    }
+                    // Parse `#[builder(note = "...")]`
+                    NestedMeta::Meta(Meta::NameValue(m)) if m.path == NOTE => {
+                        deprecation_note = get_lit_str(m);
+                    }
+                    // Parse `#[builder(since = "1.4")]`
+                    NestedMeta::Meta(Meta::NameValue(m)) if m.path == SINCE => {
+                        if let Some(lit) = get_lit_str(m) {
+                            let doc = format!(" Available since {}.", lit.value());
+                            setter_attrs.push(quote!(#[doc = #doc]));
+                        }
+                    }
+                    // Parse `#[builder(field_attr(...))]`
+                    NestedMeta::Meta(Meta::List(m)) if m.path == FIELD_ATTR => {
+                        if compact.is_some() {
+                            emit_error!(m, "`field_attr` needs a builder field of its own, which fields of a `compact` builder don't have");
+                        } else {
+                            field_attrs.extend(m.nested.iter().map(|attr| quote!(#[#attr])));
+                        }
+                    }
+                    // Parse `#[builder(setter_attr(...))]`
+                    NestedMeta::Meta(Meta::List(m)) if m.path == SETTER_ATTR => {
+                        setter_attrs.extend(m.nested.iter().map(|attr| quote!(#[#attr])));
+                    }
+                    _ => emit_error!(meta, "unknown builder attribute"),
+                }
+            }
+        }
+
+        // `Self` would name the builder inside the generated code.
+        let mut field_ty = f.ty.clone();
+        ReplaceSelf(&self_ty).visit_type_mut(&mut field_ty);
+
+        let (ty, optional) = if each.is_none() {
+            let optional_ty = extract_type_from_option(&field_ty);
+            if let Some(ty) = optional_ty {
+                (ty.clone(), true)
+            } else {
+                (field_ty.clone(), false)
+            }
+        } else {
+            let optional_ty = extract_type_from_vector(&field_ty);
+            if let Some(ty) = optional_ty {
+                (ty.clone(), false)
+            } else {
+                (field_ty.clone(), false)
+            }
+        };
+        // The wrappers' constructors can't be called in a `const fn`.
+        let wrapper = if each.is_none() && !raw && const_builder.is_none() {
+            extract_wrapper(&ty)
+        } else {
+            None
+        };
+        // Repeated fields build to an empty collection when never touched, so
+        // only plain fields that aren't wrapped in an `Option` and have no
+        // `default_with` are required.
+        let required = !optional && each.is_none() && default_with.is_none();
+
+        if let (Some(lit), Some(_)) = (&env, &each) {
+            emit_error!(lit, "`env` is not supported on repeated fields");
+            env = None;
+        }
+        if let (Some((_, meta)), Some(_)) = (&set_once, &each) {
+            emit_error!(meta, "`set_once` is not supported on repeated fields");
+            set_once = None;
+        }
+
+        if let (Some(path), false) = (&skip_by_name, keys.is_empty()) {
+            emit_error!(path, "`key` has no effect on a `skip_by_name` field");
+            keys.clear();
+        }
+        if let (Some(note), true) = (&deprecation_note, deprecated_aliases.is_empty()) {
+            emit_error!(note, "`note` goes with `deprecated_alias`");
+        }
+        let positional = required_in_constructor.is_some() && required;
+        if !deprecated_aliases.is_empty() && (positional || generic_setter.is_some()) {
+            emit_error!(
+                deprecated_aliases[0],
+                "`deprecated_alias` needs a setter to forward to, which fields passed to `builder()` and `generic_setter` fields don't have"
+            );
+            deprecated_aliases.clear();
+        }
+
+        let generic_setter = generic_setter.and_then(|path| {
+            lower_generic_setter(
+                &model,
+                f,
+                &ty,
+                each.is_some(),
+                required_in_constructor.is_some(),
+                compact.is_some(),
+            )
+            .map_err(|msg| emit_error!(path, msg))
+            .ok()
+        });
+
+        let ir = FieldIr {
+            optional,
+            required,
+            each: each.clone(),
+            name: f.ident.clone().unwrap(),
+            ty,
+            field_ty,
+            requires,
+            conflicts_with,
+            env: env.map(|lit| format!("{}{}", env_prefix, lit.value())),
+            keys,
+            skip_by_name: skip_by_name.is_some(),
+            doc: extract_doc(&f.attrs),
+            default_with,
+            // The struct-wide setting doesn't apply to repeated fields, which
+            // are meant to be added to more than once.
+            set_once: set_once
+                .map(|(s, _)| s)
+                .or(struct_set_once.filter(|_| each.is_none())),
+            generic_setter,
+            slot: if compact.is_some() {
+                Slot::Compact { index: i }
+            } else {
+                Slot::Option
+            },
+            deprecated_aliases,
+            deprecation_note,
+            wrapper,
+            cfgs,
+            field_attrs,
+            setter_attrs,
+            span: f.span(),
+        };
+        fields.push(ir);
+    }
+
+    // An infallible `build()` has no way to report a violated constraint.
+    // The constraints are dropped so that the rest still expands.
+    if let Some(path) = &required_in_constructor {
+        let constrained = !groups.is_empty()
+            || fields.iter().any(|f| {
+                !f.requires.is_empty() || !f.conflicts_with.is_empty() || f.set_once.is_some()
+            });
+        if constrained {
+            emit_error!(
+                path,
+                "`required_in_constructor` can't be combined with `requires`, `conflicts_with`, `group` or `set_once`"
+            );
+            groups.clear();
+            for f in &mut fields {
+                f.requires.clear();
+                f.conflicts_with.clear();
+                f.set_once = None;
+            }
+        }
+    }
+
+    // `build_const()` can only move plain values out of the builder.
+    let mut const_fns = const_builder.is_some();
+    if let Some(token) = &const_builder {
+        if required_in_constructor.is_some() || compact.is_some() {
+            emit_error!(
+                token,
+                "`const` can't be combined with `required_in_constructor` or `compact`"
+            );
+            const_fns = false;
+        }
+        for f in &fields {
+            if let Some(ty) = find_needs_drop(&f.field_ty) {
+                emit_error!(
+                    f.field_ty,
+                    "`{}` needs dropping, which a `const` builder's setters can't do",
+                    ty
+                );
+                const_fns = false;
+            }
+            if f.each.is_some()
+                || f.default_with.is_some()
+                || f.set_once.is_some()
+                || f.generic_setter.is_some()
+            {
+                emit_error!(
+                    f.span,
+                    "fields of a `const` builder can't use `each`, `default_with`, `set_once` or `generic_setter`"
+                );
+                const_fns = false;
+            }
+        }
+    }
+
+    let resolve_order = resolve_order(&mut fields);
+
+    Ir {
+        resolve_order,
+        fields,
+        groups,
+        set_by_name,
+        required_in_constructor: required_in_constructor.is_some(),
+        patch,
+        compact,
+        const_builder: const_builder.is_some(),
+        const_fns,
+        target: model.target,
+        builder: model.builder,
+        vis: model.vis,
+        generics: model.generics,
+    }
+}
+
+/// One comma-separated item inside a struct-level `#[builder(...)]`.
+enum StructArg {
+    Group(GroupArg),
+    /// `const`, which being a keyword isn't valid `Meta` syntax.
+    Const(Token![const]),
+    Meta(NestedMeta),
+}
+
+struct GroupArg {
+    name: LitStr,
+    exactly_one: Vec<LitStr>,
+}
+
+impl Parse for StructArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![const]) {
+            return input.parse().map(StructArg::Const);
+        }
+        if input.peek(syn::Ident) && input.peek2(token::Paren) {
+            let fork = input.fork();
+            if fork.parse::<Ident>()? == GROUP {
+                input.parse::<Ident>()?;
+                let content;
+                parenthesized!(content in input);
+                return content.parse().map(StructArg::Group);
+            }
+        }
+        input.parse().map(StructArg::Meta)
+    }
+}
+
+impl Parse for GroupArg {
+    // Parses `name = "...", exactly_one = ["...", ...]` in either order. The
+    // list of names isn't valid `Meta` syntax, so this can't go through
+    // `parse_meta` like the other attributes.
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let span = input.span();
+        let mut name = None;
+        let mut exactly_one = None;
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            if key == NAME {
+                name = Some(input.parse::<LitStr>()?);
+            } else if key == EXACTLY_ONE {
+                let content;
+                bracketed!(content in input);
+                let lits = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+                exactly_one = Some(lits.into_iter().collect());
+            } else {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "expected `name = \"...\"` or `exactly_one = [...]`",
+                ));
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(GroupArg {
+            name: name.ok_or_else(|| syn::Error::new(span, "missing `name = \"...\"`"))?,
+            exactly_one: exactly_one
+                .ok_or_else(|| syn::Error::new(span, "missing `exactly_one = [...]`"))?,
+        })
+    }
+}
+
+/// Accepts `|a, b| ...`, `function(a, b)` or a bare `function`, where `a`
+/// and `b` name the fields the default is computed from.
+fn lower_default_with(names: &[Ident], lit: LitStr) -> Option<DefaultWith> {
+    let expr: Expr = match lit.parse() {
+        Ok(expr) => expr,
+        Err(err) => {
+            emit_error!(lit, "invalid `default_with`: {}", err);
+            return None;
+        }
+    };
+
+    let (func, args) = match expr {
+        Expr::Closure(closure) => {
+            let args = closure
+                .inputs
+                .iter()
+                .map(|pat| match pat {
+                    Pat::Ident(p) => Some(p.ident.clone()),
+                    Pat::Type(PatType { pat, .. }) => match &**pat {
+                        Pat::Ident(p) => Some(p.ident.clone()),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>();
+            (Expr::Closure(closure), args)
+        }
+        Expr::Call(call) => {
+            let args = call
+                .args
+                .iter()
+                .map(|arg| match arg {
+                    Expr::Path(p) => p.path.get_ident().cloned(),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>();
+            (*call.func, args)
+        }
+        expr @ Expr::Path(_) => (expr, Some(vec![])),
+        _ => (expr, None),
+    };
+
+    let args = match args {
+        Some(args) => args,
+        None => {
+            emit_error!(
+                lit,
+                "expected a closure or function whose arguments are named after fields"
+            );
+            return None;
+        }
+    };
+
+    let deps = args
+        .iter()
+        .map(|arg| resolve_field(names, &LitStr::new(&arg.to_string(), lit.span())))
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(DefaultWith { func, deps, lit })
+}
+
+/// Orders fields so that every `default_with` comes after the fields it
+/// reads, reporting an error for each dependency cycle. The field whose
+/// `default_with` closes a cycle loses it and becomes required, which breaks
+/// the cycle so that `build()` still expands.
+fn resolve_order(fields: &mut [FieldIr]) -> Vec<usize> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        New,
+        Visiting,
+        Done,
+    }
+
+    fn visit(
+        i: usize,
+        fields: &[FieldIr],
+        marks: &mut [Mark],
+        stack: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+        broken: &mut Vec<usize>,
+    ) {
+        match marks[i] {
+            Mark::Done => return,
+            Mark::Visiting => {
+                let start = stack.iter().position(|&j| j == i).unwrap();
+                let cycle = stack[start..]
+                    .iter()
+                    .chain(Some(&i))
+                    .map(|&j| fields[j].name.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                let lit = &fields[i].default_with.as_ref().unwrap().lit;
+                emit_error!(lit, "cycle in `default_with` dependencies: {}", cycle);
+                broken.extend(stack.last());
+                return;
+            }
+            Mark::New => {}
+        }
+
+        marks[i] = Mark::Visiting;
+        stack.push(i);
+        if let Some(default_with) = &fields[i].default_with {
+            for dep in &default_with.deps {
+                let j = fields.iter().position(|f| f.name == *dep).unwrap();
+                visit(j, fields, marks, stack, order, broken);
+            }
+        }
+        stack.pop();
+        marks[i] = Mark::Done;
+        order.push(i);
+    }
+
+    let mut marks = vec![Mark::New; fields.len()];
+    let mut order = vec![];
+    let mut broken = vec![];
+    for i in 0..fields.len() {
+        visit(i, fields, &mut marks, &mut vec![], &mut order, &mut broken);
+    }
+    for i in broken {
+        let f = &mut fields[i];
+        f.default_with = None;
+        f.required = !f.optional && f.each.is_none();
+    }
+    order
+}
+
+/// Finds the type parameter a `generic_setter` field replaces. The parameter
+/// must not appear anywhere else, including in bounds, since `Target::builder()`
+/// starts out with `derive_builder::Unset` in its place.
+fn lower_generic_setter(
+    model: &Model,
+    field: &Field,
+    ty: &Type,
+    each: bool,
+    required_in_constructor: bool,
+    compact: bool,
+) -> Result<Ident, &'static str> {
+    const MSG: &str =
+        "`generic_setter` requires the field's type to be a type parameter of the struct";
+    if each || required_in_constructor || compact {
+        return Err(
+            "`generic_setter` can't be combined with `each`, `required_in_constructor` or `compact`",
+        );
+    }
+    let ident = match ty {
+        Type::Path(p) if p.qself.is_none() => p.path.get_ident().ok_or(MSG)?,
+        _ => return Err(MSG),
+    };
+    let param = model
+        .generics
+        .type_params()
+        .find(|t| t.ident == *ident)
+        .ok_or(MSG)?;
+    let bounded = !param.bounds.is_empty()
+        || param.default.is_some()
+        || model
+            .generics
+            .where_clause
+            .iter()
+            .any(|w| mentions(w.to_token_stream(), ident));
+    if bounded {
+        return Err("a `generic_setter` type parameter can't have bounds or a default on the struct; put its bounds on impl blocks instead");
+    }
+    let shared = model
+        .fields
+        .iter()
+        .filter(|f| f.ident != field.ident)
+        .any(|f| mentions(f.ty.to_token_stream(), ident));
+    if shared {
+        return Err("a `generic_setter` type parameter can't be used by other fields");
+    }
+    Ok(ident.clone())
+}
+
+/// Finds a well-known standard library type with a destructor in a field's
+/// type. Only owned positions are searched: references, raw pointers and
+/// `PhantomData` don't drop what they point at, and neither do other
+/// generic types necessarily, so those are left to the assertion in the
+/// generated code.
+fn find_needs_drop(ty: &Type) -> Option<&Ident> {
+    const NEEDS_DROP: &[&str] = &[
+        "String",
+        "Vec",
+        "Box",
+        "Rc",
+        "Arc",
+        "Cow",
+        "HashMap",
+        "HashSet",
+        "BTreeMap",
+        "BTreeSet",
+        "VecDeque",
+        "BinaryHeap",
+        "LinkedList",
+        "PathBuf",
+        "OsString",
+        "CString",
+    ];
+    match ty {
+        Type::Path(p) if p.qself.is_none() => {
+            let last = p.path.segments.last()?;
+            if NEEDS_DROP.iter().any(|n| last.ident == n) {
+                return Some(&last.ident);
+            }
+            // `Option<T>` holds a `T` by value.
+            match &last.arguments {
+                PathArguments::AngleBracketed(args) if last.ident == "Option" => {
+                    args.args.iter().find_map(|arg| match arg {
+                        GenericArgument::Type(ty) => find_needs_drop(ty),
+                        _ => None,
+                    })
+                }
+                _ => None,
+            }
+        }
+        Type::Tuple(t) => t.elems.iter().find_map(find_needs_drop),
+        Type::Array(a) => find_needs_drop(&a.elem),
+        Type::Paren(p) => find_needs_drop(&p.elem),
+        Type::Group(g) => find_needs_drop(&g.elem),
+        _ => None,
+    }
+}
+
+pub fn mentions(tokens: TokenStream, ident: &Ident) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(i) => i == *ident,
+        TokenTree::Group(g) => mentions(g.stream(), ident),
+        _ => false,
+    })
+}
+
+fn lower_set_once(meta: &Meta) -> Option<SetOnce> {
+    match meta {
+        Meta::Path(_) => Some(SetOnce::Report),
+        Meta::NameValue(m) => match get_lit_str(m)?.value().as_str() {
+            "report" => Some(SetOnce::Report),
+            "debug_panic" => Some(SetOnce::DebugPanic),
+            _ => {
+                emit_error!(m.lit, "expected `\"report\"` or `\"debug_panic\"`");
+                None
+            }
+        },
+        Meta::List(_) => {
+            emit_error!(meta, "expected `set_once` or `set_once = \"...\"`");
+            None
+        }
+    }
+}
+
+fn get_lit_str(m: &MetaNameValue) -> Option<LitStr> {
+    if let Lit::Str(lit) = &m.lit {
+        Some(lit.clone())
+    } else {
+        emit_error!(m.lit, "expected string literal");
+        None
+    }
+}
+
+/// Looks up the field named by `lit`, reporting an error at the literal when
+/// the struct has no such field.
+fn resolve_field(names: &[Ident], lit: &LitStr) -> Option<Ident> {
+    let found = names.iter().find(|n| n.unraw() == lit.value()).cloned();
+    if found.is_none() {
+        emit_error!(lit, "unknown field `{}`", lit.value());
+    }
+    found
+}
+
+fn extract_doc(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter(|a| a.path == DOC)
+        .filter_map(|a| match a.parse_meta() {
+            Ok(Meta::NameValue(MetaNameValue {
+                lit: Lit::Str(lit), ..
+            })) => Some(lit.value().trim().to_owned()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn extract_type_from_option(ty: &Type) -> Option<&Type> {
+    fn extract_type_path(ty: &Type) -> Option<&Path> {
+        match *ty {
+            Type::Path(ref typepath) if typepath.qself.is_none() => Some(&typepath.path),
+            _ => None,
+        }
+    }
+
+    fn extract_option_segment(path: &Path) -> Option<&PathSegment> {
+        let idents_of_path = path.segments.iter().fold(String::new(), |mut acc, v| {
+            acc.push_str(&v.ident.to_string());
+            acc.push('|');
+            acc
+        });
+        vec!["Option|", "std|option|Option|", "core|option|Option|"]
+            .into_iter()
+            .find(|s| idents_of_path == *s)
+            .and_then(|_| path.segments.last())
+    }
+
+    extract_type_path(ty)
+        .and_then(extract_option_segment)
+        .and_then(|path_seg| {
+            let type_params = &path_seg.arguments;
+            // It should have only on angle-bracketed param ("<String>"):
+            match *type_params {
+                PathArguments::AngleBracketed(ref params) => params.args.first(),
+                _ => None,
+            }
+        })
+        .and_then(|generic_arg| match *generic_arg {
+            GenericArgument::Type(ref ty) => Some(ty),
+            _ => None,
+        })
+}
+
+fn extract_type_from_vector(ty: &Type) -> Option<&Type> {
+    fn extract_type_path(ty: &Type) -> Option<&Path> {
+        match *ty {
+            Type::Path(ref typepath) if typepath.qself.is_none() => Some(&typepath.path),
+            _ => None,
+        }
+    }
+
+    fn extract_vector_segment(path: &Path) -> Option<&PathSegment> {
+        let idents_of_path = path.segments.iter().fold(String::new(), |mut acc, v| {
+            acc.push_str(&v.ident.to_string());
+            acc.push('|');
+            acc
+        });
+        vec!["Vec|"]
+            .into_iter()
+            .find(|s| idents_of_path == *s)
+            .and_then(|_| path.segments.last())
+    }
+
+    extract_type_path(ty)
+        .and_then(extract_vector_segment)
+        .and_then(|path_seg| {
+            let type_params = &path_seg.arguments;
+            // It should have only on angle-bracketed param ("<String>"):
+            match *type_params {
+                PathArguments::AngleBracketed(ref params) => params.args.first(),
+                _ => None,
+            }
+        })
+        .and_then(|generic_arg| match *generic_arg {
+            GenericArgument::Type(ref ty) => Some(ty),
+            _ => None,
+        })
+}
+
+/// Recognizes the smart pointers and `Cow<'_, str>`, whose setters take the
+/// value they wrap. Pointers to unsized types like `Box<dyn Error>` are left
+/// alone, since their setters can't take the inner type by value.
+fn extract_wrapper(ty: &Type) -> Option<Wrapper> {
+    let path = match ty {
+        Type::Path(typepath) if typepath.qself.is_none() => &typepath.path,
+        _ => return None,
+    };
+    let idents_of_path = path.segments.iter().fold(String::new(), |mut acc, v| {
+        acc.push_str(&v.ident.to_string());
+        acc.push('|');
+        acc
+    });
+    let new = match idents_of_path.as_str() {
+        "Box|" | "std|boxed|Box|" => quote!(::std::boxed::Box::new),
+        "Rc|" | "std|rc|Rc|" => quote!(::std::rc::Rc::new),
+        "Arc|" | "std|sync|Arc|" => quote!(::std::sync::Arc::new),
+        "Cow|" | "std|borrow|Cow|" => {
+            let is_str = match &path.segments.last()?.arguments {
+                PathArguments::AngleBracketed(params) => match params.args.last() {
+                    Some(GenericArgument::Type(Type::Path(p))) => p.path.is_ident("str"),
+                    _ => false,
+                },
+                _ => false,
+            };
+            return if is_str { Some(Wrapper::Cow) } else { None };
+        }
+        _ => return None,
+    };
+    let inner = match &path.segments.last()?.arguments {
+        PathArguments::AngleBracketed(params) if params.args.len() == 1 => {
+            match params.args.first()? {
+                GenericArgument::Type(ty) => ty,
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    match inner {
+        Type::TraitObject(_) | Type::Slice(_) => None,
+        Type::Path(p) if p.path.is_ident("str") => None,
+        _ => Some(Wrapper::Pointer {
+            new,
+            inner: Box::new(inner.clone()),
+        }),
+    }
+}
+
+/// Replaces `Self` in a field's type with the target type.
+struct ReplaceSelf<'a>(&'a Type);
+
+impl VisitMut for ReplaceSelf<'_> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        match ty {
+            Type::Path(p) if p.qself.is_none() && p.path.is_ident("Self") => *ty = self.0.clone(),
+            _ => visit_mut::visit_type_mut(self, ty),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use syn::{parse_quote, DeriveInput};
+
+    use crate::analyze::analyze;
+    use crate::lower::lower;
+
+    #[test]
+    fn test_parse() {
+        let input: DeriveInput = parse_quote! {
+            struct Target {
+                pub executable: String,
+                args: Vec<String>,
+                cwd: Option<String>,
+                env: Vec<String>,
+            }
+        };
+
+        let model = analyze(input).unwrap();
+
+        let ir = lower(model);
+
+        assert_eq!(ir.target.to_string(), "Target");
+        assert_eq!(ir.builder.to_string(), "TargetBuilder");
+
+        //assert_eq!(exe.vis, Visibility::Public);
+        assert!(ir.fields[0].required);
+
+        // assert_eq!(args.vis, Visibility::Inherited);
+        assert!(ir.fields[1].required);
+
+        // assert_eq!(cwd.vis, Visibility::Inherited);
+        assert!(!ir.fields[2].required);
+
+        // assert_eq!(env.vis, Visibility::Inherited);
+        assert!(ir.fields[3].required);
+    }
+}
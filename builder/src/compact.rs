@@ -0,0 +1,246 @@
+//! Storage for `#[builder(compact)]` builders: the fields in `MaybeUninit`
+//! slots, plus a bitmask of which slots are initialized.
+//!
+//! The generated builder holds a `Compact<(A, B, ...), uN>` and goes through
+//! the safe methods below, addressing fields by their position in the tuple,
+//! so that no `unsafe` ends up in the user's crate. The slot tuple is laid
+//! out like any other tuple, so the fields pack as tightly as they would in
+//! a struct.
+
+use std::mem::MaybeUninit;
+use std::ptr;
+
+/// Fields stored in `MaybeUninit` slots, with bit `I` of the mask set exactly
+/// when slot `I` is initialized.
+pub struct Compact<S: Slots, M: Mask> {
+    set: M,
+    slots: S::Uninit,
+}
+
+impl<S: Slots, M: Mask> Compact<S, M> {
+    /// No field set.
+    pub fn new() -> Self {
+        Compact {
+            set: M::EMPTY,
+            slots: S::uninit(),
+        }
+    }
+
+    /// Whether field `I` is set.
+    pub fn is_set<const I: usize>(&self) -> bool
+    where
+        S: Slot<I>,
+    {
+        self.set.contains(I)
+    }
+
+    pub fn get<const I: usize>(&self) -> Option<&<S as Slot<I>>::Value>
+    where
+        S: Slot<I>,
+    {
+        if self.set.contains(I) {
+            // SAFETY: the bit is only set while the slot is initialized.
+            Some(unsafe { S::slot(&self.slots).assume_init_ref() })
+        } else {
+            None
+        }
+    }
+
+    /// Stores `value` in field `I`, dropping any previous value.
+    pub fn set<const I: usize>(&mut self, value: <S as Slot<I>>::Value)
+    where
+        S: Slot<I>,
+    {
+        if self.set.contains(I) {
+            // SAFETY: the bit is only set while the slot is initialized, so
+            // assigning drops the old value.
+            unsafe { *S::slot_mut(&mut self.slots).assume_init_mut() = value };
+        } else {
+            S::slot_mut(&mut self.slots).write(value);
+            self.set.insert(I);
+        }
+    }
+
+    /// Field `I`, storing `f()` in it first if it isn't set.
+    pub fn get_mut_or_insert_with<const I: usize>(
+        &mut self,
+        f: impl FnOnce() -> <S as Slot<I>>::Value,
+    ) -> &mut <S as Slot<I>>::Value
+    where
+        S: Slot<I>,
+    {
+        if !self.set.contains(I) {
+            S::slot_mut(&mut self.slots).write(f());
+            self.set.insert(I);
+        }
+        // SAFETY: the slot was initialized above if it wasn't already.
+        unsafe { S::slot_mut(&mut self.slots).assume_init_mut() }
+    }
+
+    /// Moves the value out of field `I`, leaving it unset.
+    pub fn take<const I: usize>(&mut self) -> Option<<S as Slot<I>>::Value>
+    where
+        S: Slot<I>,
+    {
+        if self.set.contains(I) {
+            self.set.remove(I);
+            // SAFETY: the slot was initialized, and clearing its bit means it
+            // won't be read or dropped again.
+            Some(unsafe { S::slot(&self.slots).assume_init_read() })
+        } else {
+            None
+        }
+    }
+}
+
+impl<S: Slots, M: Mask> Default for Compact<S, M> {
+    fn default() -> Self {
+        Compact::new()
+    }
+}
+
+impl<S: Slots, M: Mask> Drop for Compact<S, M> {
+    fn drop(&mut self) {
+        // SAFETY: exactly the slots whose bit is set are initialized.
+        unsafe { S::drop_set(&mut self.slots, self.set.bits()) };
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A tuple of field types that can be stored in a `Compact`.
+pub trait Slots: sealed::Sealed {
+    /// The tuple with every element wrapped in `MaybeUninit`.
+    type Uninit;
+
+    fn uninit() -> Self::Uninit;
+
+    /// Drops the slots whose bit is set in `set`.
+    ///
+    /// # Safety
+    ///
+    /// Those slots have to be initialized.
+    unsafe fn drop_set(slots: &mut Self::Uninit, set: u64);
+}
+
+/// Element `I` of a `Slots` tuple.
+pub trait Slot<const I: usize>: Slots {
+    type Value;
+
+    fn slot(slots: &Self::Uninit) -> &MaybeUninit<Self::Value>;
+
+    fn slot_mut(slots: &mut Self::Uninit) -> &mut MaybeUninit<Self::Value>;
+}
+
+/// The word holding a `Compact`'s bitmask.
+pub trait Mask: sealed::Sealed + Copy {
+    const EMPTY: Self;
+
+    fn bits(self) -> u64;
+
+    fn contains(self, bit: usize) -> bool;
+
+    fn insert(&mut self, bit: usize);
+
+    fn remove(&mut self, bit: usize);
+}
+
+macro_rules! masks {
+    ($($word:ty)*) => {$(
+        impl sealed::Sealed for $word {}
+
+        impl Mask for $word {
+            const EMPTY: Self = 0;
+
+            fn bits(self) -> u64 {
+                self as u64
+            }
+
+            // Shifting by `bit` panics rather than wraps if the tuple has
+            // more elements than the word has bits.
+            fn contains(self, bit: usize) -> bool {
+                self & (1 as $word).checked_shl(bit as u32).expect("mask too narrow") != 0
+            }
+
+            fn insert(&mut self, bit: usize) {
+                *self |= (1 as $word).checked_shl(bit as u32).expect("mask too narrow");
+            }
+
+            fn remove(&mut self, bit: usize) {
+                *self &= !(1 as $word).checked_shl(bit as u32).expect("mask too narrow");
+            }
+        }
+    )*};
+}
+
+masks!(u8 u16 u32 u64);
+
+/// Implements `Slots`, and `Slot` for every element, for tuples of each
+/// length up to that of the list.
+macro_rules! tuples {
+    ([$($done:tt)*]) => {
+        tuple!($($done)*);
+    };
+    ([$($done:tt)*] $name:ident $index:tt $($rest:tt)*) => {
+        tuple!($($done)*);
+        tuples!([$($done)* $name $index] $($rest)*);
+    };
+}
+
+macro_rules! tuple {
+    ($($name:ident $index:tt)*) => {
+        impl<$($name),*> sealed::Sealed for ($($name,)*) {}
+
+        impl<$($name),*> Slots for ($($name,)*) {
+            type Uninit = ($(MaybeUninit<$name>,)*);
+
+            #[allow(clippy::unused_unit)]
+            fn uninit() -> Self::Uninit {
+                ($(MaybeUninit::<$name>::uninit(),)*)
+            }
+
+            #[allow(unused_variables)]
+            unsafe fn drop_set(slots: &mut Self::Uninit, set: u64) {
+                $(
+                    if set & (1 << $index) != 0 {
+                        ptr::drop_in_place(slots.$index.as_mut_ptr());
+                    }
+                )*
+            }
+        }
+
+        elements!([$($name)*] $($name $index)*);
+    };
+}
+
+macro_rules! elements {
+    ($all:tt) => {};
+    ([$($all:ident)*] $name:ident $index:tt $($rest:tt)*) => {
+        impl<$($all),*> Slot<$index> for ($($all,)*) {
+            type Value = $name;
+
+            fn slot(slots: &Self::Uninit) -> &MaybeUninit<$name> {
+                &slots.$index
+            }
+
+            fn slot_mut(slots: &mut Self::Uninit) -> &mut MaybeUninit<$name> {
+                &mut slots.$index
+            }
+        }
+
+        elements!([$($all)*] $($rest)*);
+    };
+}
+
+tuples!([]
+    T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7
+    T8 8 T9 9 T10 10 T11 11 T12 12 T13 13 T14 14 T15 15
+    T16 16 T17 17 T18 18 T19 19 T20 20 T21 21 T22 22 T23 23
+    T24 24 T25 25 T26 26 T27 27 T28 28 T29 29 T30 30 T31 31
+    T32 32 T33 33 T34 34 T35 35 T36 36 T37 37 T38 38 T39 39
+    T40 40 T41 41 T42 42 T43 43 T44 44 T45 45 T46 46 T47 47
+    T48 48 T49 49 T50 50 T51 51 T52 52 T53 53 T54 54 T55 55
+    T56 56 T57 57 T58 58 T59 59 T60 60 T61 61 T62 62 T63 63
+);
@@ -1,18 +1,237 @@
-use syn::{parse_macro_input, DeriveInput};
+// Crates that have the "proc-macro" crate type are only allowed to export
+// procedural macros, so the types that generated builders refer to at runtime
+// (errors and the like) live in this crate while the derive itself lives in
+// derive_builder_impl. The macro is re-exported from here so that users only
+// need to depend on the one derive_builder crate.
 
-use crate::{analyze::analyze, codegen::codegen, lower::lower};
+// The tuple impls in `compact` are generated by nested macros.
+#![recursion_limit = "256"]
 
-mod analyze;
-mod codegen;
-mod lower;
+use std::env::{self, VarError};
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::str::FromStr;
 
-pub(crate) type Ast = DeriveInput;
+pub use derive_builder_impl::Builder;
 
-#[proc_macro_derive(Builder, attributes(builder))]
-pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let ast = parse_macro_input!(input as DeriveInput) as Ast;
-    let model = analyze(ast);
-    let ir = lower(model);
-    let rust = codegen(ir);
-    rust.into()
+#[doc(hidden)]
+pub mod compact;
+
+/// Implemented by every generated builder.
+pub trait Builder {
+    /// The type this builder builds.
+    type Target;
+    /// Why building can fail.
+    type Error;
+
+    /// Builds the target from the fields set so far.
+    fn build(&mut self) -> Result<Self::Target, Self::Error>;
+}
+
+/// Implemented by every type that derives `Builder`, so that generic code
+/// can ask for "the builder for `T`". The exception is a struct with
+/// `#[builder(required_in_constructor)]`, whose `Target::builder(...)` takes
+/// arguments: its builder implements `Builder` but not `Default`, and the
+/// struct doesn't implement `Buildable`.
+///
+/// ```
+/// use derive_builder::{Buildable, Builder};
+///
+/// fn load<T: Buildable>() -> Result<T, T::Error> {
+///     let mut builder = T::builder();
+///     // ... fill in the builder generically ...
+///     builder.build()
+/// }
+///
+/// #[derive(Builder)]
+/// struct Config {
+///     retries: Option<u32>,
+/// }
+///
+/// let config: Config = load().unwrap();
+/// assert_eq!(config.retries, None);
+/// ```
+pub trait Buildable: Sized {
+    /// The generated `TargetBuilder`.
+    type Builder: Builder<Target = Self, Error = Self::Error> + Default;
+    /// Why building can fail.
+    type Error;
+
+    /// Returns an empty builder, like the generated `Target::builder()`.
+    fn builder() -> Self::Builder;
+}
+
+/// The error returned by a generated `build()` when the builder does not
+/// describe a valid value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BuildError {
+    /// A required field was never set.
+    MissingField { field: &'static str },
+    /// `field` was set but the field it `requires` was not.
+    Requires {
+        field: &'static str,
+        requires: &'static str,
+    },
+    /// `field` and the field it `conflicts_with` were both set.
+    ConflictsWith {
+        field: &'static str,
+        conflicts_with: &'static str,
+    },
+    /// An `exactly_one` group had zero or several of its fields set.
+    ExactlyOne {
+        group: &'static str,
+        fields: &'static [&'static str],
+        set: Vec<&'static str>,
+    },
+    /// A `set_once` field was set more than once.
+    SetTwice { field: &'static str },
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::MissingField { field } => {
+                write!(f, "missing value for field '{}'", field)
+            }
+            BuildError::Requires { field, requires } => {
+                write!(
+                    f,
+                    "field '{}' requires field '{}' to be set",
+                    field, requires
+                )
+            }
+            BuildError::ConflictsWith {
+                field,
+                conflicts_with,
+            } => {
+                write!(
+                    f,
+                    "field '{}' conflicts with field '{}'",
+                    field, conflicts_with
+                )
+            }
+            BuildError::ExactlyOne { group, fields, set } => {
+                write!(
+                    f,
+                    "exactly one of {:?} must be set for group '{}', found {:?}",
+                    fields, group, set
+                )
+            }
+            BuildError::SetTwice { field } => {
+                write!(f, "field '{}' was set more than once", field)
+            }
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+/// The error returned by a generated `from_env()` or `fill_from_env()` when
+/// an environment variable is set but can't be parsed into its field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvError {
+    /// The name of the offending variable, prefix included.
+    pub var: &'static str,
+    /// Why the value was rejected.
+    pub message: String,
+}
+
+impl Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid value for environment variable '{}': {}",
+            self.var, self.message
+        )
+    }
 }
+
+impl Error for EnvError {}
+
+/// Reads and parses `var`, returning `None` if it isn't set. Called from the
+/// generated `fill_from_env()`.
+#[doc(hidden)]
+pub fn __parse_env<T>(var: &'static str) -> Result<Option<T>, EnvError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = match env::var(var) {
+        Ok(value) => value,
+        Err(VarError::NotPresent) => return Ok(None),
+        Err(err) => {
+            return Err(EnvError {
+                var,
+                message: err.to_string(),
+            })
+        }
+    };
+    value.parse().map(Some).map_err(|err: T::Err| EnvError {
+        var,
+        message: err.to_string(),
+    })
+}
+
+/// The error returned by a generated `set_by_name()` or `from_pairs()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SetError {
+    /// No field or `#[builder(key = "...")]` alias has this name.
+    UnknownField { field: String },
+    /// The value couldn't be parsed into the field's type.
+    InvalidValue {
+        field: &'static str,
+        message: String,
+    },
+}
+
+impl Display for SetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SetError::UnknownField { field } => write!(f, "unknown field '{}'", field),
+            SetError::InvalidValue { field, message } => {
+                write!(f, "invalid value for field '{}': {}", field, message)
+            }
+        }
+    }
+}
+
+impl Error for SetError {}
+
+/// Parses `value` for `field`. Called from the generated `set_by_name()`.
+#[doc(hidden)]
+pub fn __parse_value<T>(field: &'static str, value: &str) -> Result<T, SetError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value.parse().map_err(|err: T::Err| SetError::InvalidValue {
+        field,
+        message: err.to_string(),
+    })
+}
+
+/// Static description of one field, as listed in a generated
+/// `TargetBuilder::FIELDS`. Useful for generating `--help` text or config
+/// schemas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldInfo {
+    /// The field's name.
+    pub name: &'static str,
+    /// The field's type as written in the struct.
+    pub ty: &'static str,
+    /// Whether `build()` fails when the field is never set.
+    pub required: bool,
+    /// The one-at-a-time setter name from `#[builder(each = "...")]`.
+    pub each: Option<&'static str>,
+    /// The field's doc comment, or an empty string.
+    pub doc: &'static str,
+    /// The expression an unset field is built from, if it isn't required.
+    pub default: Option<&'static str>,
+}
+
+/// Stands in for a type parameter whose `#[builder(generic_setter)]` hasn't
+/// been called yet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Unset;
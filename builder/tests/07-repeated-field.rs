@@ -49,7 +49,6 @@ fn main() {
         .build()
         .unwrap();
 
-    dbg!(command);
     assert_eq!(command.executable, "cargo");
     assert_eq!(command.args, vec!["build", "--release"]);
 }
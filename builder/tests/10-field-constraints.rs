@@ -0,0 +1,96 @@
+// Fields can declare relationships with each other that build() enforces.
+//
+//     #[builder(requires = "other")]        setting this field requires `other`
+//     #[builder(conflicts_with = "other")]  this field and `other` can't both be set
+//
+// and a struct-level group requires exactly one of its fields to be set:
+//
+//     #[builder(group(name = "auth", exactly_one = ["password", "token"]))]
+//
+// Violations are reported as a typed derive_builder::BuildError.
+
+use derive_builder::{BuildError, Builder};
+
+#[derive(Builder)]
+#[builder(group(name = "auth", exactly_one = ["password", "token"]))]
+pub struct Server {
+    #[builder(requires = "tls_cert")]
+    tls_key: Option<String>,
+    tls_cert: Option<String>,
+    #[builder(conflicts_with = "port")]
+    socket_path: Option<String>,
+    port: Option<u16>,
+    password: Option<String>,
+    token: Option<String>,
+    #[builder(requires = "type")]
+    subtype: Option<String>,
+    r#type: Option<String>,
+}
+
+fn main() {
+    let server = Server::builder()
+        .port(8080)
+        .token("t0k3n".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(server.port, Some(8080));
+
+    let err = Server::builder()
+        .tls_key("key.pem".to_owned())
+        .token("t0k3n".to_owned())
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(
+        err,
+        BuildError::Requires {
+            field: "tls_key",
+            requires: "tls_cert",
+        }
+    );
+
+    let err = Server::builder()
+        .socket_path("/run/app.sock".to_owned())
+        .port(8080)
+        .token("t0k3n".to_owned())
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(
+        err,
+        BuildError::ConflictsWith {
+            field: "socket_path",
+            conflicts_with: "port",
+        }
+    );
+
+    let err = Server::builder()
+        .password("hunter2".to_owned())
+        .token("t0k3n".to_owned())
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(
+        err,
+        BuildError::ExactlyOne {
+            group: "auth",
+            fields: &["password", "token"],
+            set: vec!["password", "token"],
+        }
+    );
+
+    let err = Server::builder()
+        .subtype("plain".to_owned())
+        .token("t0k3n".to_owned())
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(
+        err,
+        BuildError::Requires {
+            field: "subtype",
+            requires: "type",
+        }
+    );
+    assert!(Server::builder().build().is_err());
+}
@@ -0,0 +1,15 @@
+// Every field named by a constraint has to exist on the struct.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(group(name = "auth", exactly_one = ["password", "tokn"]))]
+pub struct Server {
+    #[builder(requires = "tls_crt")]
+    tls_key: Option<String>,
+    tls_cert: Option<String>,
+    password: Option<String>,
+    token: Option<String>,
+}
+
+fn main() {}
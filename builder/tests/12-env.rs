@@ -0,0 +1,57 @@
+// Fields marked #[builder(env = "...")] can be loaded from environment
+// variables through the generated TargetBuilder::from_env() and
+// fill_from_env(). The field type (or the type inside its Option) has to
+// implement FromStr. A struct-level #[builder(env_prefix = "...")] is
+// prepended to every variable name.
+//
+// Only fields that are still unset get filled, so values set explicitly on
+// the builder take precedence over the environment.
+
+use derive_builder::{Builder, EnvError};
+use std::env;
+
+#[derive(Builder)]
+#[builder(env_prefix = "APP_")]
+pub struct Service {
+    #[builder(env = "HOST")]
+    host: String,
+    #[builder(env = "PORT")]
+    port: u16,
+    #[builder(env = "WORKERS")]
+    workers: Option<usize>,
+    name: String,
+}
+
+fn main() {
+    env::set_var("APP_HOST", "localhost");
+    env::set_var("APP_PORT", "8080");
+    env::remove_var("APP_WORKERS");
+
+    let service = ServiceBuilder::from_env()
+        .unwrap()
+        .name("api".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(service.host, "localhost");
+    assert_eq!(service.port, 8080);
+    assert_eq!(service.workers, None);
+
+    let service = Service::builder()
+        .port(9090)
+        .name("api".to_owned())
+        .fill_from_env()
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(service.port, 9090);
+
+    env::set_var("APP_WORKERS", "many");
+    let err = ServiceBuilder::from_env().err().unwrap();
+    assert_eq!(
+        err,
+        EnvError {
+            var: "APP_WORKERS",
+            message: "invalid digit found in string".to_owned(),
+        }
+    );
+}
@@ -0,0 +1,68 @@
+// A struct-level #[builder(set_by_name)] generates string-keyed setters for
+// overrides coming from config files or `--set key=value` arguments:
+//
+//     fn set_by_name(&mut self, field: &str, value: &str) -> Result<(), SetError>
+//     fn from_pairs<I, K, V>(pairs: I) -> Result<Self, SetError>
+//
+// Values are parsed with FromStr, repeated fields append each parsed value,
+// and #[builder(key = "...")] adds alias names for a field. A raw identifier
+// like `r#type` is set by its plain name. Fields whose type isn't FromStr opt
+// out with #[builder(skip_by_name)] and keep only their setter.
+
+use derive_builder::{Builder, SetError};
+use std::time::Duration;
+
+#[derive(Builder)]
+#[builder(set_by_name)]
+pub struct Command {
+    #[builder(key = "exe", key = "program")]
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    retries: Option<u32>,
+    r#type: Option<String>,
+    #[builder(skip_by_name)]
+    timeout: Option<Duration>,
+}
+
+fn main() {
+    let command = CommandBuilder::from_pairs(vec![
+        ("exe", "cargo"),
+        ("args", "build"),
+        ("args", "--release"),
+        ("retries", "3"),
+        ("type", "release"),
+    ])
+    .unwrap()
+    .build()
+    .unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.retries, Some(3));
+    assert_eq!(command.r#type.as_deref(), Some("release"));
+
+    let mut builder = Command::builder();
+    builder.set_by_name("program", "rustc").unwrap();
+    assert_eq!(builder.build().unwrap().executable, "rustc");
+
+    assert_eq!(
+        builder.set_by_name("cwd", ".."),
+        Err(SetError::UnknownField {
+            field: "cwd".to_owned(),
+        })
+    );
+    assert_eq!(
+        builder.set_by_name("timeout", "30"),
+        Err(SetError::UnknownField {
+            field: "timeout".to_owned(),
+        })
+    );
+    builder.timeout(Duration::from_secs(30));
+    assert_eq!(
+        builder.set_by_name("retries", "-1"),
+        Err(SetError::InvalidValue {
+            field: "retries",
+            message: "invalid digit found in string".to_owned(),
+        })
+    );
+}
@@ -0,0 +1,57 @@
+// Every builder exposes static metadata about the target's fields through
+// TargetBuilder::FIELDS, which is enough to generate help text or a config
+// schema without maintaining them by hand.
+
+use derive_builder::{Builder, FieldInfo};
+
+#[derive(Builder)]
+pub struct Command {
+    /// The program to run.
+    executable: String,
+    /// Arguments passed to the program,
+    /// in order.
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<std::path::PathBuf>,
+    r#type: Option<String>,
+}
+
+fn main() {
+    assert_eq!(
+        CommandBuilder::FIELDS,
+        &[
+            FieldInfo {
+                name: "executable",
+                ty: "String",
+                required: true,
+                each: None,
+                doc: "The program to run.",
+                default: None,
+            },
+            FieldInfo {
+                name: "args",
+                ty: "Vec<String>",
+                required: false,
+                each: Some("arg"),
+                doc: "Arguments passed to the program,\nin order.",
+                default: Some("Default::default()"),
+            },
+            FieldInfo {
+                name: "current_dir",
+                ty: "Option<std::path::PathBuf>",
+                required: false,
+                each: None,
+                doc: "",
+                default: Some("None"),
+            },
+            FieldInfo {
+                name: "type",
+                ty: "Option<String>",
+                required: false,
+                each: None,
+                doc: "",
+                default: Some("None"),
+            },
+        ]
+    );
+}
@@ -0,0 +1,53 @@
+// With #[builder(required_in_constructor)] on the struct, every required
+// field becomes a positional argument of Target::builder(...) and only the
+// remaining fields get setters. Since nothing required can be missing,
+// build() returns the target directly instead of a Result. Through the
+// derive_builder::Builder trait, building a second time reports the
+// positional fields as missing, since the first build took them; calling
+// build() itself a second time panics.
+
+use derive_builder::{BuildError, Builder};
+use std::panic;
+
+#[derive(Builder)]
+#[builder(required_in_constructor)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    timeout: u64,
+}
+
+#[derive(Builder)]
+#[builder(required_in_constructor)]
+pub struct Job {
+    r#type: String,
+}
+
+fn main() {
+    let command: Command = Command::builder("cargo".to_owned(), 30)
+        .arg("build".to_owned())
+        .current_dir("..".to_owned())
+        .build();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build"]);
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+    assert_eq!(command.timeout, 30);
+
+    let mut builder = Command::builder("cargo".to_owned(), 30);
+    let command = Builder::build(&mut builder).unwrap();
+    assert_eq!(command.executable, "cargo");
+    let err = Builder::build(&mut builder).err().unwrap();
+    assert_eq!(err, BuildError::MissingField { field: "executable" });
+
+    panic::set_hook(Box::new(|_| {}));
+    let mut builder = Job::builder("batch".to_owned());
+    assert_eq!(builder.build().r#type, "batch");
+    let err = panic::catch_unwind(move || builder.build()).err().unwrap();
+    assert_eq!(
+        err.downcast_ref::<String>().map(String::as_str),
+        Some("field 'type' was already taken by a previous build()")
+    );
+}
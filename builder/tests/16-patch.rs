@@ -0,0 +1,60 @@
+// A struct-level #[builder(patch)] lets a builder act as a sparse update of
+// an existing value. Every field type must implement Clone and PartialEq.
+//
+//     fn apply_to(&self, target: &mut Target)    overwrite the fields that were set
+//     fn append_to(&self, target: &mut Target)   same, but extend repeated fields
+//     fn diff(old: &Target, new: &Target) -> TargetBuilder
+//
+// diff() records only the fields that changed, so applying it to `old`
+// reproduces `new`.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Clone, Debug, PartialEq)]
+#[builder(patch)]
+pub struct Server {
+    host: String,
+    port: u16,
+    #[builder(each = "alias")]
+    aliases: Vec<String>,
+    tls: Option<bool>,
+}
+
+fn main() {
+    let mut server = Server::builder()
+        .host("localhost".to_owned())
+        .port(80)
+        .alias("www".to_owned())
+        .tls(true)
+        .build()
+        .unwrap();
+
+    let mut patch = Server::builder();
+    patch.port(8080).alias("api".to_owned());
+    patch.apply_to(&mut server);
+    assert_eq!(server.host, "localhost");
+    assert_eq!(server.port, 8080);
+    assert_eq!(server.aliases, vec!["api"]);
+
+    patch.append_to(&mut server);
+    assert_eq!(server.aliases, vec!["api", "api"]);
+
+    let old = server.clone();
+    let new = Server {
+        port: 443,
+        tls: None,
+        ..old.clone()
+    };
+    let diff = ServerBuilder::diff(&old, &new);
+    let mut updated = old.clone();
+    diff.apply_to(&mut updated);
+    assert_eq!(updated, new);
+
+    let mut untouched = Server {
+        host: "example.com".to_owned(),
+        ..old.clone()
+    };
+    diff.apply_to(&mut untouched);
+    assert_eq!(untouched.host, "example.com");
+    assert_eq!(untouched.port, 443);
+}
@@ -0,0 +1,50 @@
+// #[builder(default_with = "...")] computes a field from other fields when it
+// was never set. The value may be a closure whose parameters are named after
+// the fields it reads, a call such as `function(field_a, field_b)`, or a bare
+// function taking nothing. Those fields are passed by reference after they
+// have been resolved themselves, so defaults can build on each other.
+
+use derive_builder::Builder;
+use std::time::Duration;
+
+fn timeout_for(retries: &u32) -> Duration {
+    Duration::from_secs(10 * u64::from(*retries + 1))
+}
+
+fn default_retries() -> u32 {
+    2
+}
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(default_with = "|executable: &String| executable.to_uppercase()")]
+    display_name: String,
+    executable: String,
+    #[builder(default_with = "timeout_for(retries)")]
+    timeout: Duration,
+    #[builder(default_with = "default_retries")]
+    retries: u32,
+    #[builder(default_with = "|display_name: &String, retries| Some(format!(\"{display_name} x{retries}\"))")]
+    label: Option<String>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.display_name, "CARGO");
+    assert_eq!(command.retries, 2);
+    assert_eq!(command.timeout, Duration::from_secs(30));
+    assert_eq!(command.label.as_deref(), Some("CARGO x2"));
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .display_name("Cargo".to_owned())
+        .retries(0)
+        .build()
+        .unwrap();
+    assert_eq!(command.display_name, "Cargo");
+    assert_eq!(command.timeout, Duration::from_secs(10));
+    assert_eq!(command.label.as_deref(), Some("Cargo x0"));
+}
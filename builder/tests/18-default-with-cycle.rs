@@ -0,0 +1,13 @@
+// Defaults that depend on each other in a cycle can never be resolved.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Window {
+    #[builder(default_with = "|height: &u32| height * 2")]
+    width: u32,
+    #[builder(default_with = "|width: &u32| width / 2")]
+    height: u32,
+}
+
+fn main() {}
@@ -0,0 +1,52 @@
+// Fields marked #[builder(set_once)] refuse to be overwritten: setting one a
+// second time makes build() fail with BuildError::SetTwice naming the field.
+// With #[builder(set_once = "debug_panic")] the second set panics right away
+// in debug builds instead. Either form can also go on the struct to cover
+// every field except repeated ones.
+
+use derive_builder::{BuildError, Builder};
+use std::panic;
+
+#[derive(Builder)]
+#[builder(set_once)]
+pub struct Plugin {
+    name: String,
+    #[builder(each = "hook")]
+    hooks: Vec<String>,
+    #[builder(set_once = "debug_panic")]
+    priority: Option<i32>,
+    r#type: Option<String>,
+}
+
+fn main() {
+    let plugin = Plugin::builder()
+        .name("audit".to_owned())
+        .hook("pre".to_owned())
+        .hook("post".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(plugin.hooks, vec!["pre", "post"]);
+
+    let err = Plugin::builder()
+        .name("audit".to_owned())
+        .name("metrics".to_owned())
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err, BuildError::SetTwice { field: "name" });
+
+    let err = Plugin::builder()
+        .name("audit".to_owned())
+        .r#type("sink".to_owned())
+        .r#type("source".to_owned())
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err, BuildError::SetTwice { field: "type" });
+
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(|| {
+        Plugin::builder().priority(1).priority(2);
+    });
+    assert_eq!(result.is_err(), cfg!(debug_assertions));
+}
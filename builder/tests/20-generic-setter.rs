@@ -0,0 +1,73 @@
+// Generic structs get a builder with the same type parameters. Marking a
+// field whose type is a bare type parameter with #[builder(generic_setter)]
+// makes Target::builder() start with derive_builder::Unset in place of that
+// parameter, and gives the field an owned setter that returns a builder over
+// the argument's type. The parameter is then inferred from the setter call
+// instead of being annotated up front.
+//
+// A set_once generic_setter field still counts the second call as setting it
+// twice, even though the value changes type.
+//
+// Since Target<Unset> has to be a valid type, the parameter can't have bounds
+// on the struct itself; put them on the impl blocks that need them instead.
+
+use derive_builder::{BuildError, Builder, Unset};
+
+pub trait Transport {
+    fn name(&self) -> &'static str;
+}
+
+pub struct TcpTransport;
+
+impl Transport for TcpTransport {
+    fn name(&self) -> &'static str {
+        "tcp"
+    }
+}
+
+#[derive(Builder)]
+pub struct Client<'a, T> {
+    #[builder(generic_setter)]
+    transport: T,
+    host: &'a str,
+    retries: Option<u32>,
+}
+
+impl<'a, T: Transport> Client<'a, T> {
+    fn describe(&self) -> String {
+        format!("{}://{}", self.transport.name(), self.host)
+    }
+}
+
+#[derive(Builder)]
+pub struct Wrapper<T: Clone> {
+    #[builder(each = "item")]
+    items: Vec<T>,
+}
+
+#[derive(Builder)]
+#[builder(set_once)]
+pub struct Session<T> {
+    #[builder(generic_setter)]
+    transport: T,
+}
+
+fn main() {
+    let mut builder: ClientBuilder<Unset> = Client::builder();
+    builder.host("example.com").retries(3);
+
+    let client = builder.transport(TcpTransport).build().unwrap();
+    assert_eq!(client.describe(), "tcp://example.com");
+    assert_eq!(client.retries, Some(3));
+
+    let wrapper = Wrapper::builder().item(1).item(2).build().unwrap();
+    assert_eq!(wrapper.items, vec![1, 2]);
+
+    let err = Session::builder()
+        .transport(1u8)
+        .transport("tcp")
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err, BuildError::SetTwice { field: "transport" });
+}
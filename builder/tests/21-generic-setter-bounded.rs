@@ -0,0 +1,14 @@
+// A generic_setter parameter starts out as derive_builder::Unset, so it can't
+// carry bounds on the struct.
+
+use derive_builder::Builder;
+
+pub trait Transport {}
+
+#[derive(Builder)]
+pub struct Client<T: Transport> {
+    #[builder(generic_setter)]
+    transport: T,
+}
+
+fn main() {}
@@ -0,0 +1,39 @@
+// Every derived type implements derive_builder::Buildable and its builder
+// implements derive_builder::Builder, so generic code such as test fixtures
+// or config loaders can be written once for any type with a builder. The
+// builder also implements Default.
+
+use derive_builder::{BuildError, Buildable, Builder};
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+pub struct Limits {
+    max_connections: Option<u32>,
+}
+
+fn load<T: Buildable>() -> Result<T, T::Error> {
+    T::builder().build()
+}
+
+fn build_twice<B: Builder>(builder: &mut B) -> (Result<B::Target, B::Error>, Result<B::Target, B::Error>) {
+    (builder.build(), builder.build())
+}
+
+fn main() {
+    let limits: Limits = load().unwrap();
+    assert_eq!(limits.max_connections, None);
+
+    let err = load::<Command>().err().unwrap();
+    assert_eq!(err, BuildError::MissingField { field: "executable" });
+
+    let mut builder = CommandBuilder::default();
+    builder.executable("cargo".to_owned());
+    let (first, second) = build_twice(&mut builder);
+    assert_eq!(first.unwrap().executable, "cargo");
+    assert!(second.is_err());
+}
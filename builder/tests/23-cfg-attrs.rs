@@ -0,0 +1,59 @@
+// The compiler evaluates #[cfg] and #[cfg_attr] on a field before the derive
+// sees it: a field whose cfg is false never reaches the derive, and a true
+// cfg_attr has already been replaced by the attributes it applies, so a
+// #[cfg_attr(..., builder(...))] works like a plain #[builder(...)]. Any #[cfg]
+// that does reach the derive is still repeated on everything generated for
+// the field. Other attributes can be forwarded with
+// #[builder(field_attr(...))] for the builder's field and
+// #[builder(setter_attr(...))] for its setters.
+
+use derive_builder::{BuildError, Builder};
+
+#[derive(Debug, Builder)]
+#[builder(group(name = "transport", exactly_one = ["plain", "socket"]))]
+pub struct Server {
+    host: String,
+    #[builder(setter_attr(doc(hidden), deprecated = "set `host` instead"))]
+    address: Option<String>,
+    plain: Option<bool>,
+    #[cfg(all())]
+    socket: Option<String>,
+    #[cfg(any())]
+    #[builder(requires = "host")]
+    tls: Option<String>,
+    #[cfg(any())]
+    #[builder(each = "cert")]
+    certs: Vec<String>,
+    #[cfg(all())]
+    #[cfg_attr(all(), builder(each = "header"))]
+    #[builder(field_attr(allow(dead_code)))]
+    headers: Vec<String>,
+}
+
+fn main() {
+    #[allow(deprecated)]
+    let server = Server::builder()
+        .host("localhost".to_owned())
+        .address("127.0.0.1".to_owned())
+        .plain(true)
+        .header("x-a".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(server.host, "localhost");
+    assert_eq!(server.address.as_deref(), Some("127.0.0.1"));
+    assert_eq!(server.headers, vec!["x-a"]);
+
+    let err = Server::builder()
+        .host("localhost".to_owned())
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        BuildError::ExactlyOne {
+            group: "transport",
+            fields: &["plain", "socket"],
+            set: vec![],
+        }
+    );
+    assert_eq!(ServerBuilder::FIELDS.len(), 5);
+}
@@ -0,0 +1,48 @@
+// Setters for Box<T>, Rc<T> and Arc<T> fields take the T and wrap it, and
+// setters for Cow<'a, str> fields take anything that converts into one, like
+// a &str or a String. #[builder(setter(raw))] keeps a setter taking the
+// field's own type. Self in a field's type still means the target, so
+// recursive types get a builder too.
+
+use derive_builder::Builder;
+use std::borrow::Cow;
+use std::rc::Rc;
+use std::sync::Arc;
+
+#[derive(Debug, Builder)]
+pub struct Node<'a> {
+    label: Cow<'a, str>,
+    weight: Box<u32>,
+    shared: Arc<Vec<u8>>,
+    #[builder(setter(raw))]
+    cache: Rc<String>,
+    next: Option<Box<Self>>,
+    error: Option<Box<dyn std::error::Error>>,
+}
+
+fn main() {
+    let leaf = Node::builder()
+        .label(String::from("leaf"))
+        .weight(1)
+        .shared(vec![1, 2])
+        .cache(Rc::new("cached".to_owned()))
+        .build()
+        .unwrap();
+    assert!(leaf.next.is_none());
+
+    let root = Node::builder()
+        .label("root")
+        .weight(2)
+        .shared(Vec::new())
+        .cache(Rc::clone(&leaf.cache))
+        .next(leaf)
+        .error(Box::from("broken"))
+        .build()
+        .unwrap();
+    assert_eq!(root.label, "root");
+    assert_eq!(*root.weight, 2);
+    assert!(root.shared.is_empty());
+    assert_eq!(Rc::strong_count(&root.cache), 2);
+    assert_eq!(root.next.unwrap().label, "leaf");
+    assert_eq!(root.error.unwrap().to_string(), "broken");
+}
@@ -0,0 +1,48 @@
+// Besides the one-at-a-time setter, a repeated field gets extend_<field> to
+// add several items at once and clear_<field> to start over, and its
+// whole-collection setter takes any iterator of items rather than exactly a
+// Vec. A repeated field that is never touched builds to an empty collection.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(each = "env")]
+    env: Vec<String>,
+    #[builder(each = "feature")]
+    features: Vec<&'static str>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .extend_args(["--release", "--locked"].iter().map(|s| s.to_string()))
+        .arg("--quiet".to_owned())
+        .features(["std", "serde"])
+        .build()
+        .unwrap();
+    assert_eq!(
+        command.args,
+        vec!["build", "--release", "--locked", "--quiet"]
+    );
+    assert!(command.env.is_empty());
+    assert_eq!(command.features, vec!["std", "serde"]);
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .clear_args()
+        .arg("test".to_owned())
+        .extend_env(Some("RUST_LOG=debug".to_owned()))
+        .feature("std")
+        .clear_features()
+        .build()
+        .unwrap();
+    assert_eq!(command.args, vec!["test"]);
+    assert_eq!(command.env, vec!["RUST_LOG=debug"]);
+    assert!(command.features.is_empty());
+}
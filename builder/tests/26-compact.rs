@@ -0,0 +1,83 @@
+// #[builder(compact)] stores fields in MaybeUninit slots tracked by a bitmask
+// instead of in Options. The builder behaves exactly the same, including
+// dropping whatever it holds when it is dropped half-filled, and values that
+// get overwritten. The slots live in a type from derive_builder, so the
+// generated code contains no unsafe and works in crates that forbid it.
+
+#![forbid(unsafe_code)]
+
+use derive_builder::{BuildError, Builder};
+use std::rc::Rc;
+
+#[derive(Debug, Builder)]
+#[builder(compact, patch)]
+#[builder(group(name = "payload", exactly_one = ["body", "empty"]))]
+pub struct Message {
+    id: u64,
+    #[builder(setter(raw))]
+    owner: Rc<()>,
+    #[builder(requires = "id")]
+    body: Option<String>,
+    empty: Option<bool>,
+    #[builder(each = "tag")]
+    tags: Vec<String>,
+    #[builder(default_with = "|id: &u64| id * 2")]
+    priority: u64,
+}
+
+fn main() {
+    let owner = Rc::new(());
+
+    let message = Message::builder()
+        .id(7)
+        .owner(Rc::clone(&owner))
+        .body("hello".to_owned())
+        .tag("a".to_owned())
+        .extend_tags(vec!["b".to_owned()])
+        .build()
+        .unwrap();
+    assert_eq!(message.id, 7);
+    assert_eq!(message.body.as_deref(), Some("hello"));
+    assert_eq!(message.empty, None);
+    assert_eq!(message.tags, vec!["a", "b"]);
+    assert_eq!(message.priority, 14);
+    assert_eq!(Rc::strong_count(&owner), 2);
+    drop(message);
+    assert_eq!(Rc::strong_count(&owner), 1);
+
+    // Overwriting a value drops the old one.
+    let mut builder = Message::builder();
+    builder.owner(Rc::clone(&owner)).owner(Rc::clone(&owner));
+    assert_eq!(Rc::strong_count(&owner), 2);
+
+    // A failed build leaves what it didn't take, and dropping the builder
+    // drops that too.
+    assert_eq!(
+        builder.build().unwrap_err(),
+        BuildError::ExactlyOne {
+            group: "payload",
+            fields: &["body", "empty"],
+            set: vec![],
+        }
+    );
+    builder.empty(true).tag("x".to_owned());
+    assert_eq!(
+        builder.build().unwrap_err(),
+        BuildError::MissingField { field: "id" }
+    );
+    assert_eq!(Rc::strong_count(&owner), 2);
+    drop(builder);
+    assert_eq!(Rc::strong_count(&owner), 1);
+
+    let mut message = Message::builder()
+        .id(1)
+        .owner(Rc::clone(&owner))
+        .empty(true)
+        .build()
+        .unwrap();
+    let mut patch = MessageBuilder::default();
+    patch.id(2).tag("new".to_owned());
+    patch.apply_to(&mut message);
+    assert_eq!(message.id, 2);
+    assert_eq!(message.tags, vec!["new"]);
+}
@@ -0,0 +1,105 @@
+// #[builder(const)] turns Target::builder(), the setters and a new
+// build_const() into const fns, so that a builder can initialize a constant
+// or a static. The setters take and return the builder by value. Where
+// build() would return an error, build_const() panics, which fails the
+// build when it happens at compile time.
+//
+// Fields only have to be free of a destructor themselves: borrowing a String
+// or holding a PhantomData<String> is fine.
+
+use derive_builder::Builder;
+use std::marker::PhantomData;
+use std::panic;
+
+#[derive(Debug, PartialEq, Builder)]
+#[builder(const)]
+#[builder(group(name = "listen", exactly_one = ["port", "socket"]))]
+pub struct Config {
+    host: &'static str,
+    port: Option<u16>,
+    socket: Option<&'static str>,
+    #[builder(requires = "port")]
+    tls: Option<bool>,
+    workers: Option<usize>,
+}
+
+#[derive(Builder)]
+#[builder(const)]
+pub struct Banner {
+    title: &'static String,
+    lines: &'static [String],
+    marker: Option<PhantomData<String>>,
+    r#type: u8,
+}
+
+#[derive(Builder)]
+#[builder(const)]
+#[builder(group(name = "mode", exactly_one = ["set", "other"]))]
+pub struct Toggle {
+    set: Option<u8>,
+    other: Option<u8>,
+}
+
+const TOGGLE: Toggle = Toggle::builder().set(1).build_const();
+
+static TITLE: String = String::new();
+
+const BANNER: Banner = Banner::builder()
+    .title(&TITLE)
+    .lines(&[])
+    .r#type(1)
+    .build_const();
+
+static DEFAULT_CFG: Config = Config::builder().host("localhost").port(80).build_const();
+
+const LOCAL_CFG: Config = Config::builder()
+    .host("localhost")
+    .socket("/run/app.sock")
+    .workers(4)
+    .build_const();
+
+fn main() {
+    assert_eq!(DEFAULT_CFG.host, "localhost");
+    assert_eq!(DEFAULT_CFG.port, Some(80));
+    assert_eq!(DEFAULT_CFG.workers, None);
+    assert_eq!(LOCAL_CFG.socket, Some("/run/app.sock"));
+    assert_eq!(LOCAL_CFG.workers, Some(4));
+
+    assert!(BANNER.title.is_empty());
+    assert!(BANNER.lines.is_empty());
+    assert_eq!(BANNER.marker, None);
+    assert_eq!(BANNER.r#type, 1);
+    assert_eq!(TOGGLE.set, Some(1));
+    assert_eq!(TOGGLE.other, None);
+
+    // build() still works, and agrees with build_const().
+    let mut builder = Config::builder().host("localhost").port(80);
+    assert_eq!(builder.build().unwrap(), DEFAULT_CFG);
+
+    panic::set_hook(Box::new(|_| {}));
+    let err = panic::catch_unwind(|| Config::builder().port(80).build_const()).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<&str>(),
+        Some(&"missing value for field 'host'")
+    );
+    let err = panic::catch_unwind(|| {
+        Config::builder()
+            .host("localhost")
+            .socket("/run/app.sock")
+            .tls(true)
+            .build_const()
+    })
+    .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<&str>(),
+        Some(&"field 'tls' requires field 'port' to be set")
+    );
+
+    let err = panic::catch_unwind(|| Banner::builder().title(&TITLE).lines(&[]).build_const())
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.downcast_ref::<&str>(),
+        Some(&"missing value for field 'type'")
+    );
+}
@@ -0,0 +1,14 @@
+// Every field of a #[builder(const)] struct has to be something a const fn
+// can overwrite, which rules out types with a destructor like String.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(const)]
+pub struct Config {
+    host: String,
+    port: u16,
+    tags: Option<Vec<&'static str>>,
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+// #[builder(debug_expansion)] writes what the derive generated for a struct
+// to derive_builder/<Target>.expanded.rs, pretty-printed, along with the
+// derive's intermediate representations in derive_builder/<Target>.debug.txt.
+// They go to OUT_DIR if the crate has a build script and to the target
+// directory otherwise. Setting DERIVE_BUILDER_DEBUG=1 in the environment does
+// the same for every derive, and DERIVE_BUILDER_DEBUG=Config,Command for
+// just the named ones. Cargo doesn't track environment variables read by a
+// proc macro, so changing DERIVE_BUILDER_DEBUG doesn't by itself re-expand a
+// crate that is already built; touch a source file or run cargo clean.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(debug_expansion)]
+pub struct Config {
+    host: String,
+    port: Option<u16>,
+}
+
+fn main() {
+    let config = Config::builder()
+        .host("localhost".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(config.host, "localhost");
+    assert_eq!(config.port, None);
+}
@@ -0,0 +1,33 @@
+// A mistake in a #[builder] attribute is reported without throwing away the
+// rest of the builder: everything the derive could make sense of is still
+// generated, so code using the builder gets no follow-up errors and IDEs
+// keep completing its setters while the attribute is fixed.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(set_by_nmae)]
+pub struct Command {
+    executable: String,
+    #[builder(eahc = "arg")]
+    args: Vec<String>,
+    #[builder(each = "env", requires = "current")]
+    env: Vec<String>,
+    #[builder(each = "1tag")]
+    tags: Vec<String>,
+    #[builder(default_with = "|timeout: &u32| *timeout")]
+    retries: u32,
+    #[builder(default_with = "|retries: &u32| *retries")]
+    timeout: u32,
+}
+
+fn main() {
+    let _command = Command::builder()
+        .executable("cargo".to_owned())
+        .args(vec!["build".to_owned()])
+        .env("RUST_LOG=debug".to_owned())
+        .tags(vec!["ci".to_owned()])
+        .retries(3)
+        .build()
+        .unwrap();
+}
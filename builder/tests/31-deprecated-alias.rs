@@ -0,0 +1,46 @@
+// #[builder(deprecated_alias = "old_name")] keeps a setter under a field's
+// old name after the field is renamed, marked #[deprecated] and forwarding
+// to the new setter, so that callers keep compiling. #[builder(note = "...")]
+// replaces the default deprecation note, and #[builder(since = "1.4")]
+// records in the setters' docs when a field was added.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(deprecated_alias = "exe", deprecated_alias = "program")]
+    executable: String,
+    #[builder(each = "arg", deprecated_alias = "arguments", note = "use `args` or `arg`")]
+    args: Vec<String>,
+    #[builder(each = "env", deprecated_alias = "add_env", since = "1.4")]
+    env: Vec<String>,
+    #[builder(since = "1.2")]
+    current_dir: Option<String>,
+}
+
+#[allow(deprecated)]
+fn old_style() -> Command {
+    Command::builder()
+        .exe("cargo".to_owned())
+        .arguments(vec!["build".to_owned()])
+        .add_env("RUST_LOG=debug".to_owned())
+        .build()
+        .unwrap()
+}
+
+fn main() {
+    let command = old_style();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build"]);
+    assert_eq!(command.env, vec!["RUST_LOG=debug"]);
+    assert_eq!(command.current_dir, None);
+
+    #[allow(deprecated)]
+    let command = Command::builder()
+        .program("rustc".to_owned())
+        .current_dir("..".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "rustc");
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+}
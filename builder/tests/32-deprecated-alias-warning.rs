@@ -0,0 +1,21 @@
+// Calling a setter by a deprecated alias gets the usual deprecation warning,
+// turned into an error here.
+
+#![deny(deprecated)]
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(deprecated_alias = "exe")]
+    executable: String,
+    #[builder(deprecated_alias = "dir", note = "use `current_dir` instead")]
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let _ = Command::builder()
+        .exe("cargo".to_owned())
+        .dir("..".to_owned())
+        .build();
+}
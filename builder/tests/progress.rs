@@ -9,7 +9,30 @@ fn tests() {
     t.pass("tests/06-optional-field.rs");
     t.pass("tests/07-repeated-field.rs");
     //t.compile_fail("tests/08-unrecognized-attribute.rs");
-    //t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/10-field-constraints.rs");
+    t.compile_fail("tests/11-constraint-unknown-field.rs");
+    t.pass("tests/12-env.rs");
+    t.pass("tests/13-set-by-name.rs");
+    t.pass("tests/14-field-info.rs");
+    t.pass("tests/15-required-in-constructor.rs");
+    t.pass("tests/16-patch.rs");
+    t.pass("tests/17-default-with.rs");
+    t.compile_fail("tests/18-default-with-cycle.rs");
+    t.pass("tests/19-set-once.rs");
+    t.pass("tests/20-generic-setter.rs");
+    t.compile_fail("tests/21-generic-setter-bounded.rs");
+    t.pass("tests/22-buildable.rs");
+    t.pass("tests/23-cfg-attrs.rs");
+    t.pass("tests/24-smart-pointers.rs");
+    t.pass("tests/25-each-bulk.rs");
+    t.pass("tests/26-compact.rs");
+    t.pass("tests/27-const-builder.rs");
+    t.compile_fail("tests/28-const-needs-drop.rs");
+    t.pass("tests/29-debug-expansion.rs");
+    t.compile_fail("tests/30-partial-expansion.rs");
+    t.pass("tests/31-deprecated-alias.rs");
+    t.compile_fail("tests/32-deprecated-alias-warning.rs");
 }
 
 // #[cfg(test)]
@@ -0,0 +1,38 @@
+use proc_macro2::Ident;
+use proc_macro_error::emit_error;
+use syn::{Attribute, Data, Fields, Generics, Variant};
+
+use crate::Ast;
+
+#[derive(Debug)]
+pub struct Model {
+    pub target: Ident,
+    pub attrs: Vec<Attribute>,
+    pub generics: Generics,
+    pub body: Body,
+}
+
+#[derive(Debug)]
+pub enum Body {
+    Struct(Fields),
+    Enum(Vec<Variant>),
+}
+
+/// Returns `None` for input that `Debug` can't be derived for, after
+/// reporting why.
+pub fn analyze(ast: Ast) -> Option<Model> {
+    let body = match ast.data {
+        Data::Struct(data) => Body::Struct(data.fields),
+        Data::Enum(data) => Body::Enum(data.variants.into_iter().collect()),
+        Data::Union(_) => {
+            emit_error!(ast.ident, "`CustomDebug` can't be derived for unions");
+            return None;
+        }
+    };
+    Some(Model {
+        target: ast.ident,
+        attrs: ast.attrs,
+        generics: ast.generics,
+        body,
+    })
+}
@@ -0,0 +1,131 @@
+use std::collections::BTreeSet;
+
+use proc_macro2::Ident;
+use quote::ToTokens;
+use syn::visit::{self, Visit};
+use syn::{parse_quote, Generics, Path, Type, TypePath, WherePredicate};
+
+/// Infers the `where` predicates the `Debug` impl needs from the types of the
+/// fields that go through `bound`, which is `Debug` for fields formatted as
+/// usual.
+///
+/// A type parameter gets `T: Debug` only when some field mentions it
+/// directly. Mentions inside `PhantomData` don't count, and associated types
+/// rooted in a parameter, like `T::Value`, are bounded themselves instead of
+/// their parameter.
+pub fn infer<'a>(
+    generics: &Generics,
+    tys: impl IntoIterator<Item = &'a Type>,
+    bound: &Path,
+) -> Vec<WherePredicate> {
+    let params: Vec<&Ident> = generics.type_params().map(|param| &param.ident).collect();
+    let mut visitor = Mentions {
+        params: &params,
+        direct: BTreeSet::new(),
+        associated: vec![],
+    };
+    for ty in tys {
+        visitor.visit_type(ty);
+    }
+
+    let Mentions {
+        direct, associated, ..
+    } = visitor;
+    let direct = params.iter().filter(|param| direct.contains(**param));
+    let mut bounds: Vec<WherePredicate> =
+        direct.map(|param| parse_quote!(#param: #bound)).collect();
+    bounds.extend(
+        associated
+            .into_iter()
+            .map(|path| parse_quote!(#path: #bound)),
+    );
+    bounds
+}
+
+struct Mentions<'a> {
+    params: &'a [&'a Ident],
+    direct: BTreeSet<Ident>,
+    /// Associated type paths, deduplicated, in the order they were found.
+    associated: Vec<TypePath>,
+}
+
+impl Mentions<'_> {
+    fn is_param(&self, ident: &Ident) -> bool {
+        self.params.contains(&ident)
+    }
+
+    /// `T::Value` or `<T as Trait>::Value` for a type parameter `T`.
+    fn is_associated(&self, ty: &TypePath) -> bool {
+        match &ty.qself {
+            Some(qself) => match &*qself.ty {
+                Type::Path(inner) => {
+                    inner.qself.is_none()
+                        && inner.path.get_ident().is_some_and(|i| self.is_param(i))
+                }
+                _ => false,
+            },
+            None => {
+                ty.path.leading_colon.is_none()
+                    && ty.path.segments.len() > 1
+                    && self.is_param(&ty.path.segments[0].ident)
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Mentions<'_> {
+    fn visit_type_path(&mut self, ty: &'ast TypePath) {
+        let last = ty.path.segments.last();
+        if ty.qself.is_none() && last.is_some_and(|s| s.ident == "PhantomData") {
+            // `PhantomData<T>` is `Debug` whatever `T` is.
+            return;
+        }
+        if self.is_associated(ty) {
+            let tokens = ty.to_token_stream().to_string();
+            if !self
+                .associated
+                .iter()
+                .any(|seen| seen.to_token_stream().to_string() == tokens)
+            {
+                self.associated.push(ty.clone());
+            }
+            return;
+        }
+        if let Some(ident) = ty.path.get_ident() {
+            if ty.qself.is_none() && self.is_param(ident) {
+                self.direct.insert(ident.clone());
+                return;
+            }
+        }
+        visit::visit_type_path(self, ty);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use quote::quote;
+    use syn::{parse_quote, Generics, Type};
+
+    use super::infer;
+
+    #[test]
+    fn test_infer() {
+        let generics: Generics = parse_quote!(<T: Trait, U, V>);
+        let tys: Vec<Type> = vec![
+            parse_quote!(PhantomData<U>),
+            parse_quote!(Vec<T::Value>),
+            parse_quote!(Option<<T as Trait>::Value>),
+            parse_quote!(HashMap<T::Value, V>),
+        ];
+        let bounds = infer(&generics, &tys, &parse_quote!(::core::fmt::Debug));
+        assert_eq!(
+            quote!(#(#bounds),*).to_string(),
+            quote!(
+                V: ::core::fmt::Debug,
+                T::Value: ::core::fmt::Debug,
+                <T as Trait>::Value: ::core::fmt::Debug
+            )
+            .to_string(),
+        );
+    }
+}
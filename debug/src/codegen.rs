@@ -0,0 +1,177 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::ext::IdentExt;
+use syn::Member;
+
+use crate::lower::{FieldIr, Ir, Redact, Skip, Style, VariantIr};
+
+pub type Rust = proc_macro::TokenStream;
+
+pub fn codegen(ir: Ir) -> Rust {
+    let Ir {
+        target,
+        generics,
+        bounds,
+        variants,
+    } = &ir;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut where_clause = where_clause
+        .cloned()
+        .unwrap_or_else(|| syn::parse_quote!(where));
+    where_clause.predicates.extend(bounds.iter().cloned());
+
+    let arms = variants.iter().map(to_arm);
+    let redact_hash = variants
+        .iter()
+        .flat_map(|v| &v.fields)
+        .any(|f| f.redact == Some(Redact::Hash))
+        .then(to_redact_hash);
+
+    let expanded = quote! {
+        impl #impl_generics ::core::fmt::Debug for #target #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                #redact_hash
+                match *self {
+                    #( #arms )*
+                }
+            }
+        }
+    };
+
+    expanded.into()
+}
+
+/// `Self::Variant { a: ref __self_0, .. } => f.debug_struct("Variant")...`
+fn to_arm(v: &VariantIr) -> TokenStream {
+    let path = match &v.ident {
+        Some(ident) => quote!(Self::#ident),
+        None => quote!(Self),
+    };
+    let name = &v.name;
+    let bindings = v.fields.iter().map(|f| {
+        let FieldIr {
+            member, binding, ..
+        } = f;
+        quote!(#member: ref #binding)
+    });
+    let body = match v.style {
+        _ if v.transparent => {
+            let value = to_value(&v.fields[0]);
+            quote!(::core::fmt::Debug::fmt(#value, f))
+        }
+        Style::Unit => quote!(f.write_str(#name)),
+        Style::Tuple => to_builder(v, quote!(debug_tuple), |_| quote!()),
+        Style::Named => to_builder(v, quote!(debug_struct), |f| {
+            // `r#type` prints as `type`, like the derived `Debug` does.
+            let label = match &f.member {
+                Member::Named(ident) => ident.unraw().to_string(),
+                Member::Unnamed(index) => index.index.to_string(),
+            };
+            quote!(#label,)
+        }),
+    };
+    quote! {
+        #path { #( #bindings, )* } => #body,
+    }
+}
+
+/// `f.debug_struct("Name").field("a", __self_0).finish()`, or built up one
+/// statement at a time when fields may be skipped, finishing with
+/// `finish_non_exhaustive()` if any were.
+fn to_builder(
+    v: &VariantIr,
+    method: TokenStream,
+    label: impl Fn(&FieldIr) -> TokenStream,
+) -> TokenStream {
+    let name = &v.name;
+    if v.fields.iter().all(|f| f.skip.is_none()) {
+        let fields = v.fields.iter().map(|f| {
+            let label = label(f);
+            let value = to_value(f);
+            quote!(.field(#label #value))
+        });
+        return quote!(f.#method(#name) #( #fields )* .finish());
+    }
+    let fields = v.fields.iter().map(|f| {
+        let label = label(f);
+        let value = to_value(f);
+        let binding = &f.binding;
+        match &f.skip {
+            None => quote!(__debug.field(#label #value);),
+            Some(Skip::Always) => quote!(__exhaustive = false;),
+            Some(Skip::If(predicate)) => quote! {
+                if #predicate(#binding) {
+                    __exhaustive = false;
+                } else {
+                    __debug.field(#label #value);
+                }
+            },
+        }
+    });
+    quote!({
+        let mut __debug = f.#method(#name);
+        let mut __exhaustive = true;
+        #( #fields )*
+        if __exhaustive {
+            __debug.finish()
+        } else {
+            __debug.finish_non_exhaustive()
+        }
+    })
+}
+
+fn to_value(f: &FieldIr) -> TokenStream {
+    let FieldIr {
+        binding,
+        format,
+        redact,
+        ..
+    } = f;
+    match redact {
+        Some(Redact::Placeholder) => {
+            return quote!(&::core::format_args!("<redacted>"));
+        }
+        Some(Redact::Len) => {
+            return quote!(&::core::format_args!("<redacted len={}>", #binding.len()));
+        }
+        Some(Redact::Hash) => {
+            return quote!(&::core::format_args!("<redacted #{:08x}>", __redact_hash(#binding)));
+        }
+        None => {}
+    }
+    match format {
+        Some(format) => quote_spanned! {format.span()=>
+            &::core::format_args!(#format, #binding)
+        },
+        None => quote!(#binding),
+    }
+}
+
+/// FNV-1a folded to 32 bits. It only needs to be stable, so that the same
+/// value shows up as the same hash from run to run, and to not be the value.
+/// Being short and unsalted, it doesn't hide low-entropy values from anyone
+/// willing to hash guesses.
+fn to_redact_hash() -> TokenStream {
+    quote! {
+        fn __redact_hash<T: ?::core::marker::Sized + ::core::hash::Hash>(value: &T) -> u32 {
+            struct Fnv(u64);
+
+            impl ::core::hash::Hasher for Fnv {
+                fn finish(&self) -> u64 {
+                    self.0
+                }
+
+                fn write(&mut self, bytes: &[u8]) {
+                    for byte in bytes {
+                        self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x0100_0000_01b3);
+                    }
+                }
+            }
+
+            let mut hasher = Fnv(0xcbf2_9ce4_8422_2325);
+            ::core::hash::Hash::hash(value, &mut hasher);
+            let hash = ::core::hash::Hasher::finish(&hasher);
+            (hash ^ (hash >> 32)) as u32
+        }
+    }
+}
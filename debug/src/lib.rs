@@ -1,8 +1,23 @@
-use proc_macro::TokenStream;
+use proc_macro_error::proc_macro_error;
+use syn::{parse_macro_input, DeriveInput};
 
-#[proc_macro_derive(CustomDebug)]
-pub fn derive(input: TokenStream) -> TokenStream {
-    let _ = input;
+use crate::{analyze::analyze, codegen::codegen, lower::lower};
 
-    unimplemented!()
+mod analyze;
+mod bound;
+mod codegen;
+mod lower;
+
+pub(crate) type Ast = DeriveInput;
+
+#[proc_macro_derive(CustomDebug, attributes(debug))]
+#[proc_macro_error]
+pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput) as Ast;
+    let model = match analyze(ast) {
+        Some(model) => model,
+        None => return proc_macro::TokenStream::new(),
+    };
+    let ir = lower(model);
+    codegen(ir)
 }
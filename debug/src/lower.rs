@@ -0,0 +1,487 @@
+use std::fmt;
+use std::fmt::Display;
+
+use proc_macro2::Ident;
+use proc_macro_error::emit_error;
+use quote::{format_ident, ToTokens};
+use syn::ext::IdentExt;
+use syn::parse_quote;
+use syn::punctuated::Punctuated;
+use syn::{
+    Attribute, ExprPath, Field, Fields, Generics, Index, Lit, LitStr, Member, Meta, MetaNameValue,
+    NestedMeta, Path, Token, Type, Variant, WherePredicate,
+};
+
+use crate::analyze::{Body, Model};
+use crate::bound;
+
+struct Symbol(&'static str);
+
+const DEBUG: Symbol = Symbol("debug");
+const BOUND: Symbol = Symbol("bound");
+const NAME: Symbol = Symbol("name");
+const TRANSPARENT: Symbol = Symbol("transparent");
+const REDACT: Symbol = Symbol("redact");
+const REDACT_ALL_EXCEPT: Symbol = Symbol("redact_all_except");
+const SKIP: Symbol = Symbol("skip");
+const SKIP_IF: Symbol = Symbol("skip_if");
+
+impl PartialEq<Symbol> for Path {
+    fn eq(&self, word: &Symbol) -> bool {
+        self.is_ident(word.0)
+    }
+}
+impl PartialEq<Symbol> for &Path {
+    fn eq(&self, word: &Symbol) -> bool {
+        self.is_ident(word.0)
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Ir {
+    pub target: Ident,
+    pub generics: Generics,
+    /// Extra `where` predicates for the `Debug` impl.
+    pub bounds: Vec<WherePredicate>,
+    /// The variants of an enum, or a struct as its single variant.
+    pub variants: Vec<VariantIr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VariantIr {
+    /// `None` for a struct, which is matched as `Self { .. }`.
+    pub ident: Option<Ident>,
+    /// The name shown in the output, from `#[debug(name = "...")]` or the
+    /// struct or variant name.
+    pub name: String,
+    pub style: Style,
+    pub fields: Vec<FieldIr>,
+    /// `#[debug(transparent)]`: format as the single field alone.
+    pub transparent: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Style {
+    Named,
+    Tuple,
+    Unit,
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldIr {
+    pub member: Member,
+    /// What the field is bound to when matching on `self`. These are never
+    /// the field names, which could shadow the formatter.
+    pub binding: Ident,
+    pub ty: Type,
+    /// The format string from `#[debug = "..."]`, already checked to have
+    /// exactly one placeholder.
+    pub format: Option<LitStr>,
+    /// From `#[debug(bound = "...")]`, standing in for the bounds this
+    /// field's type would otherwise contribute.
+    pub bound: Option<Bound>,
+    pub redact: Option<Redact>,
+    pub skip: Option<Skip>,
+}
+
+/// When a field is left out of the output.
+#[derive(Debug, Clone)]
+pub enum Skip {
+    /// `#[debug(skip)]`
+    Always,
+    /// `#[debug(skip_if = "Option::is_none")]`, called with `&field`.
+    If(ExprPath),
+}
+
+/// How a `#[debug(redact)]` field is shown in place of its value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Redact {
+    /// `<redacted>`
+    Placeholder,
+    /// `<redacted len=8>`, from the value's `len()`.
+    Len,
+    /// `<redacted #1a2b3c4d>`, from a stable hash of the value, so equal
+    /// values can be matched up across log lines.
+    Hash,
+}
+
+/// The where-predicates of a `bound = "..."`, with the literal they came from.
+#[derive(Debug, Clone)]
+pub struct Bound {
+    pub lit: LitStr,
+    pub predicates: Vec<WherePredicate>,
+}
+
+pub fn lower(model: Model) -> Ir {
+    let mut struct_bound = None;
+    let mut transparent = None;
+    let mut redact_all_except = None;
+    for attr in model.attrs.iter().filter(|a| a.path == DEBUG) {
+        for meta in parse_list(attr) {
+            match &meta {
+                // Parse `#[debug(bound = "T::Value: Debug")]`
+                NestedMeta::Meta(Meta::NameValue(m)) if m.path == BOUND => {
+                    set_bound(&mut struct_bound, m);
+                }
+                // Parse `#[debug(transparent)]`
+                NestedMeta::Meta(Meta::Path(p)) if p == TRANSPARENT => {
+                    transparent = Some(p.clone())
+                }
+                // Parse `#[debug(redact_all_except(name, id))]`
+                NestedMeta::Meta(Meta::List(list)) if list.path == REDACT_ALL_EXCEPT => {
+                    let except = list.nested.iter().filter_map(to_member).collect::<Vec<_>>();
+                    redact_all_except = Some(except);
+                }
+                _ => emit_error!(meta, "unknown debug attribute"),
+            }
+        }
+    }
+
+    let mut variants = match &model.body {
+        Body::Struct(fields) => {
+            if let Some(p) = &transparent {
+                if fields.len() != 1 {
+                    emit_error!(p, "`transparent` needs a struct with exactly one field");
+                }
+            }
+            let mut variant = VariantIr {
+                ident: None,
+                name: model.target.unraw().to_string(),
+                style: to_style(fields),
+                fields: fields.iter().enumerate().map(lower_field).collect(),
+                transparent: transparent.is_some() && fields.len() == 1,
+            };
+            if variant.transparent && variant.fields[0].skip.is_some() {
+                emit_error!(transparent, "`transparent` can't skip its only field");
+                variant.transparent = false;
+            }
+            vec![variant]
+        }
+        Body::Enum(variants) => {
+            if let Some(p) = &transparent {
+                emit_error!(p, "`transparent` only applies to structs");
+            }
+            variants.iter().map(lower_variant).collect()
+        }
+    };
+
+    if let Some(except) = redact_all_except {
+        redact_all(&mut variants, &except);
+    }
+
+    // A field that's never shown needs nothing from its type.
+    let fields = variants
+        .iter()
+        .flat_map(|v| &v.fields)
+        .filter(|f| !matches!(f.skip, Some(Skip::Always)));
+    let bounds = match struct_bound {
+        Some(Bound { predicates, .. }) => {
+            for bound in fields.filter_map(|f| f.bound.as_ref()) {
+                emit_error!(
+                    bound.lit,
+                    "this field's `bound` would be ignored, since the struct-level `bound` replaces all inferred bounds";
+                    help = "move these predicates into the struct-level `bound`"
+                );
+            }
+            predicates
+        }
+        None => {
+            let inferred = fields.clone().filter(|f| f.bound.is_none());
+            let debug = inferred.clone().filter(|f| f.redact.is_none());
+            let hash = inferred.filter(|f| f.redact == Some(Redact::Hash));
+            let mut bounds = bound::infer(
+                &model.generics,
+                debug.map(|f| &f.ty),
+                &parse_quote!(::core::fmt::Debug),
+            );
+            bounds.extend(bound::infer(
+                &model.generics,
+                hash.map(|f| &f.ty),
+                &parse_quote!(::core::hash::Hash),
+            ));
+            for f in fields {
+                bounds.extend(f.bound.iter().flat_map(|b| b.predicates.clone()));
+            }
+            bounds
+        }
+    };
+
+    Ir {
+        target: model.target,
+        generics: model.generics,
+        bounds,
+        variants,
+    }
+}
+
+fn lower_variant(v: &Variant) -> VariantIr {
+    let mut name = None;
+    for attr in v.attrs.iter().filter(|a| a.path == DEBUG) {
+        for meta in parse_list(attr) {
+            match &meta {
+                // Parse `#[debug(name = "NotFound")]`
+                NestedMeta::Meta(Meta::NameValue(m)) if m.path == NAME => match &m.lit {
+                    Lit::Str(lit) if name.is_some() => emit_error!(lit, "duplicate `name`"),
+                    Lit::Str(lit) => name = Some(lit.value()),
+                    lit => emit_error!(lit, "expected string literal"),
+                },
+                _ => emit_error!(meta, "unknown debug attribute"),
+            }
+        }
+    }
+    VariantIr {
+        ident: Some(v.ident.clone()),
+        name: name.unwrap_or_else(|| v.ident.unraw().to_string()),
+        style: to_style(&v.fields),
+        fields: v.fields.iter().enumerate().map(lower_field).collect(),
+        transparent: false,
+    }
+}
+
+fn to_style(fields: &Fields) -> Style {
+    match fields {
+        Fields::Named(_) => Style::Named,
+        Fields::Unnamed(_) => Style::Tuple,
+        Fields::Unit => Style::Unit,
+    }
+}
+
+fn lower_field((i, f): (usize, &Field)) -> FieldIr {
+    let mut format = None;
+    let mut bound = None;
+    let mut redact = None;
+    let mut skip = None;
+    for attr in f.attrs.iter().filter(|a| a.path == DEBUG) {
+        match attr.parse_meta() {
+            // Parse `#[debug = "0b{:08b}"]`
+            Ok(Meta::NameValue(m)) => match m.lit {
+                Lit::Str(lit) => match check_format(&lit.value()) {
+                    Ok(()) => format = Some(lit),
+                    Err(msg) => emit_error!(lit, msg),
+                },
+                lit => emit_error!(lit, "expected string literal"),
+            },
+            Ok(Meta::List(list)) => {
+                for meta in list.nested {
+                    match &meta {
+                        // Parse `#[debug(bound = "T: Display")]`
+                        NestedMeta::Meta(Meta::NameValue(m)) if m.path == BOUND => {
+                            set_bound(&mut bound, m);
+                        }
+                        // Parse `#[debug(redact)]`
+                        NestedMeta::Meta(Meta::Path(p)) if p == REDACT => {
+                            redact = Some((Redact::Placeholder, meta.clone()));
+                        }
+                        // Parse `#[debug(redact = "hash")]`
+                        NestedMeta::Meta(Meta::NameValue(m)) if m.path == REDACT => match &m.lit {
+                            Lit::Str(lit) if lit.value() == "len" => {
+                                redact = Some((Redact::Len, meta.clone()));
+                            }
+                            Lit::Str(lit) if lit.value() == "hash" => {
+                                redact = Some((Redact::Hash, meta.clone()));
+                            }
+                            lit => emit_error!(lit, "expected `\"len\"` or `\"hash\"`"),
+                        },
+                        // Parse `#[debug(skip)]`
+                        NestedMeta::Meta(Meta::Path(p)) if p == SKIP => {
+                            set_skip(&mut skip, Skip::Always, &meta);
+                        }
+                        // Parse `#[debug(skip_if = "Option::is_none")]`
+                        NestedMeta::Meta(Meta::NameValue(m)) if m.path == SKIP_IF => match &m.lit {
+                            Lit::Str(lit) => match lit.parse() {
+                                Ok(path) => set_skip(&mut skip, Skip::If(path), &meta),
+                                Err(_) => emit_error!(
+                                    lit,
+                                    "expected a path to a function taking a reference to the field and returning `bool`, like `Option::is_none`"
+                                ),
+                            },
+                            lit => emit_error!(lit, "expected string literal"),
+                        },
+                        _ => emit_error!(meta, "unknown debug attribute"),
+                    }
+                }
+            }
+            Ok(meta) => emit_error!(meta, "expected `debug = \"...\"` or `debug(...)`"),
+            Err(err) => emit_error!(err),
+        }
+    }
+    if let (Some((_, meta)), Some(_)) = (&redact, &format) {
+        emit_error!(meta, "a redacted field can't also have a format string");
+    }
+    if let (Some((Skip::Always, meta)), true) = (&skip, format.is_some() || redact.is_some()) {
+        emit_error!(
+            meta,
+            "a skipped field is never shown, so it can't be formatted or redacted"
+        );
+    }
+    let member = match &f.ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(Index::from(i)),
+    };
+    FieldIr {
+        member,
+        binding: format_ident!("__self_{}", i),
+        ty: f.ty.clone(),
+        format,
+        bound,
+        redact: redact.map(|(redact, _)| redact),
+        skip: skip.map(|(skip, _)| skip),
+    }
+}
+
+fn set_skip(slot: &mut Option<(Skip, NestedMeta)>, skip: Skip, meta: &NestedMeta) {
+    if slot.is_some() {
+        emit_error!(
+            meta,
+            "`skip` and `skip_if` can be given only once, and not together"
+        );
+    } else {
+        *slot = Some((skip, meta.clone()));
+    }
+}
+
+fn to_member(nested: &NestedMeta) -> Option<Member> {
+    match nested {
+        NestedMeta::Meta(Meta::Path(p)) if p.get_ident().is_some() => {
+            Some(Member::Named(p.get_ident().cloned().unwrap()))
+        }
+        NestedMeta::Lit(Lit::Int(lit)) => match lit.base10_parse() {
+            Ok(index) => Some(Member::Unnamed(Index {
+                index,
+                span: lit.span(),
+            })),
+            Err(err) => {
+                emit_error!(err);
+                None
+            }
+        },
+        _ => {
+            emit_error!(nested, "expected a field name or tuple index");
+            None
+        }
+    }
+}
+
+/// Redacts every field not named in `except` that isn't already redacted some
+/// other way.
+fn redact_all(variants: &mut [VariantIr], except: &[Member]) {
+    for member in except {
+        let fields = variants.iter().flat_map(|v| &v.fields);
+        if !fields.clone().any(|f| f.member == *member) {
+            emit_error!(member, "unknown field `{}`", member.to_token_stream());
+        }
+    }
+    for f in variants.iter_mut().flat_map(|v| &mut v.fields) {
+        if f.redact.is_none() && !except.contains(&f.member) {
+            f.redact = Some(Redact::Placeholder);
+        }
+    }
+}
+
+fn parse_list(attr: &Attribute) -> Vec<NestedMeta> {
+    match attr.parse_meta() {
+        Ok(Meta::List(list)) => list.nested.into_iter().collect(),
+        Ok(meta) => {
+            emit_error!(meta, "expected `debug(...)`");
+            vec![]
+        }
+        Err(err) => {
+            emit_error!(err);
+            vec![]
+        }
+    }
+}
+
+/// Parses the where-predicates of a `bound = "..."` into `slot`. An empty
+/// string is allowed and means no bounds at all.
+fn set_bound(slot: &mut Option<Bound>, m: &MetaNameValue) {
+    let lit = match &m.lit {
+        Lit::Str(lit) => lit,
+        lit => return emit_error!(lit, "expected string literal"),
+    };
+    if slot.is_some() {
+        return emit_error!(lit, "duplicate `bound`");
+    }
+    match lit.parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated) {
+        Ok(predicates) => {
+            *slot = Some(Bound {
+                lit: lit.clone(),
+                predicates: predicates.into_iter().collect(),
+            })
+        }
+        Err(err) => emit_error!(
+            lit,
+            "invalid `bound`: {}", err;
+            help = "expected where-predicates, like `T: Debug, T::Value: Debug`"
+        ),
+    }
+}
+
+/// Checks that a `#[debug = "..."]` format string has exactly one
+/// placeholder, which the field's value fills.
+fn check_format(format: &str) -> Result<(), String> {
+    let mut placeholders = 0;
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+            }
+            '{' => {
+                let mut spec = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => spec.push(c),
+                        None => return Err("unterminated `{` in format string".to_owned()),
+                    }
+                }
+                let arg = spec.split(':').next().unwrap_or_default().trim();
+                if !arg.is_empty() && arg != "0" {
+                    return Err(format!(
+                        "the field's value is the only argument, so `{{{}}}` should be `{{}}` or `{{0}}`",
+                        spec
+                    ));
+                }
+                placeholders += 1;
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+            }
+            '}' => {
+                return Err("unmatched `}` in format string; use `}}` for a literal `}`".to_owned())
+            }
+            _ => {}
+        }
+    }
+    match placeholders {
+        1 => Ok(()),
+        0 => Err("format string has no `{}` for the field's value".to_owned()),
+        n => Err(format!(
+            "format string has {} placeholders, but only the field's value to fill them",
+            n
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::check_format;
+
+    #[test]
+    fn test_check_format() {
+        assert!(check_format("0b{:08b}").is_ok());
+        assert!(check_format("{0:?} {{literal}}").is_ok());
+        assert!(check_format("no placeholder").is_err());
+        assert!(check_format("{} and {}").is_err());
+        assert!(check_format("{name}").is_err());
+        assert!(check_format("{:x").is_err());
+        assert!(check_format("x}").is_err());
+    }
+}
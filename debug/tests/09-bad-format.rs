@@ -0,0 +1,20 @@
+// A `#[debug = "..."]` format string is filled with exactly one argument, the
+// field's value, so it needs exactly one placeholder that refers to it. These
+// are rejected when the derive expands, pointing at the offending string,
+// rather than as an error from inside the generated `format_args!`.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Field {
+    #[debug = "constant"]
+    name: &'static str,
+    #[debug = "{:x} or {:o}"]
+    bitmask: u8,
+    #[debug = "{width}"]
+    width: u8,
+    #[debug = "{:x"]
+    height: u8,
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+// Bounds are checked when the derive expands. Each `bound` must parse as a
+// comma-separated list of where-predicates, and a field-level `bound` can't
+// be combined with a struct-level one, which replaces all inferred bounds.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(bound = "T Debug")]
+pub struct Invalid<T> {
+    value: T,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "T: ::std::fmt::Debug")]
+pub struct Conflicting<T, U> {
+    value: T,
+    #[debug(bound = "U: ::std::fmt::Debug")]
+    other: U,
+}
+
+fn main() {}
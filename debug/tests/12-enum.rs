@@ -0,0 +1,70 @@
+// Enums format each variant the way the standard derive would: unit variants
+// as their name, tuple variants with `debug_tuple` and struct variants with
+// `debug_struct`. Field attributes work inside variants, and a variant-level
+// `#[debug(name = "...")]` changes the name that's shown.
+//
+// Bounds are inferred from the fields of every variant, so `T` needs `Debug`
+// here only because `Io` holds one, and `Never` has no bounds at all.
+//
+// Raw identifiers are shown without their `r#`, as with the standard derive.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+#[derive(CustomDebug)]
+pub enum Error<T, U> {
+    Eof,
+    Io(T, #[debug = "{:#x}"] u32),
+    #[debug(name = "BadHeader")]
+    Header {
+        #[debug = "0b{:08b}"]
+        flags: u8,
+        f: &'static str,
+    },
+    Marker(PhantomData<U>),
+    #[allow(non_camel_case_types)]
+    r#try {
+        r#type: u8,
+    },
+}
+
+#[derive(CustomDebug)]
+pub enum Never {}
+
+#[derive(CustomDebug)]
+#[allow(non_camel_case_types)]
+pub struct r#match {
+    r#type: u8,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    assert_debug::<Error<String, NotDebug>>();
+    assert_debug::<Never>();
+
+    let eof = Error::<&str, NotDebug>::Eof;
+    assert_eq!(format!("{:?}", eof), "Eof");
+
+    let io = Error::<&str, NotDebug>::Io("disk", 255);
+    assert_eq!(format!("{:?}", io), r#"Io("disk", 0xff)"#);
+
+    let header = Error::<&str, NotDebug>::Header {
+        flags: 0b00011100,
+        f: "F",
+    };
+    assert_eq!(
+        format!("{:?}", header),
+        r#"BadHeader { flags: 0b00011100, f: "F" }"#,
+    );
+
+    let marker = Error::<&str, NotDebug>::Marker(PhantomData);
+    assert!(format!("{:?}", marker).starts_with("Marker(PhantomData<"));
+
+    let r#try = Error::<&str, NotDebug>::r#try { r#type: 1 };
+    assert_eq!(format!("{:?}", r#try), "try { type: 1 }");
+    assert_eq!(format!("{:?}", r#match { r#type: 2 }), "match { type: 2 }");
+}
@@ -0,0 +1,37 @@
+// Tuple structs format with `debug_tuple` and unit structs as their name.
+//
+// A single-field struct marked `#[debug(transparent)]` formats as its field
+// alone, so a newtype ID shows up in logs as `42` rather than `UserId(42)`.
+// Field attributes still apply to that field.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Point(i32, #[debug = "{:+}"] i32);
+
+#[derive(CustomDebug)]
+pub struct Marker;
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct UserId(u64);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Mask {
+    #[debug = "0b{:04b}"]
+    bits: u8,
+}
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Wrapper<T>(T);
+
+fn main() {
+    assert_eq!(format!("{:?}", Point(1, 2)), "Point(1, +2)");
+    assert_eq!(format!("{:?}", Marker), "Marker");
+    assert_eq!(format!("{:?}", UserId(42)), "42");
+    assert_eq!(format!("{:?}", Mask { bits: 5 }), "0b0101");
+    assert_eq!(format!("{:?}", Wrapper("id")), r#""id""#);
+    assert_eq!(format!("{:#?}", Wrapper(UserId(7))), "7");
+}
@@ -0,0 +1,21 @@
+// `#[debug(transparent)]` forwards to a single field, so it's rejected on
+// structs with any other number of fields, and on enums.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Pair(u8, u8);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Unit;
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub enum Either {
+    Left(u8),
+    Right(u8),
+}
+
+fn main() {}
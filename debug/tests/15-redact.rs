@@ -0,0 +1,79 @@
+// Secrets shouldn't end up in logs through `{:?}`. A `#[debug(redact)]` field
+// shows a placeholder instead of its value; `redact = "len"` shows only its
+// length; `redact = "hash"` shows a short stable hash, so the same value can
+// be recognized across log lines without being revealed. The hash is 32 bits
+// and unsalted, so a low-entropy secret like a password or PIN can be found
+// again by hashing guesses; only hash high-entropy values such as tokens or
+// generated keys.
+//
+// A struct-level `#[debug(redact_all_except(...))]` redacts every field but the
+// ones listed, unless a field asks to be redacted some other way.
+//
+// Redacted fields need no `Debug`, so they contribute no `Debug` bounds; a
+// hashed field needs `Hash` instead.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+pub struct Login<T> {
+    user: &'static str,
+    #[debug(redact)]
+    password: T,
+    #[debug(redact = "len")]
+    otp: String,
+    #[debug(redact = "hash")]
+    token: String,
+}
+
+#[derive(CustomDebug)]
+#[debug(redact_all_except(id, kind))]
+pub struct Card {
+    id: u32,
+    kind: &'static str,
+    number: String,
+    #[debug(redact = "len")]
+    cvc: String,
+}
+
+#[derive(CustomDebug)]
+#[debug(redact_all_except(0))]
+pub struct Secret(&'static str, &'static str);
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    assert_debug::<Login<NotDebug>>();
+
+    let login = |token: &str| Login {
+        user: "root",
+        password: "hunter2",
+        otp: "123456".to_owned(),
+        token: token.to_owned(),
+    };
+    let debug = format!("{:?}", login("abc"));
+    let prefix = r#"Login { user: "root", password: <redacted>, otp: <redacted len=6>, token: <redacted #"#;
+    assert!(debug.starts_with(prefix), "{}", debug);
+    assert!(!debug.contains("hunter2") && !debug.contains("abc"));
+
+    let hash = &debug[prefix.len()..prefix.len() + 8];
+    assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(debug, format!("{:?}", login("abc")));
+    assert_ne!(debug, format!("{:?}", login("abd")));
+
+    let card = Card {
+        id: 7,
+        kind: "visa",
+        number: "4111111111111111".to_owned(),
+        cvc: "123".to_owned(),
+    };
+    assert_eq!(
+        format!("{:?}", card),
+        r#"Card { id: 7, kind: "visa", number: <redacted>, cvc: <redacted len=3> }"#,
+    );
+
+    let secret = Secret("name", "value");
+    assert_eq!(format!("{:?}", secret), r#"Secret("name", <redacted>)"#);
+}
@@ -0,0 +1,23 @@
+// Redaction modes are checked when the derive expands: `redact` takes only
+// `"len"` or `"hash"`, can't be combined with a format string, and the fields
+// listed in `redact_all_except` must exist.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Login {
+    #[debug(redact = "mask")]
+    password: String,
+    #[debug = "{:x}"]
+    #[debug(redact)]
+    pin: u32,
+}
+
+#[derive(CustomDebug)]
+#[debug(redact_all_except(id, name))]
+pub struct Account {
+    id: u32,
+    token: String,
+}
+
+fn main() {}
@@ -0,0 +1,72 @@
+// `#[debug(skip)]` leaves a field out of the output, and
+// `#[debug(skip_if = "path")]` leaves it out whenever the function, called
+// with a reference to the field, returns true. Output that omits anything
+// ends in `..`, from `finish_non_exhaustive()`, so readers know.
+//
+// A skipped field is never shown, so its type needs no `Debug`.
+
+use derive_debug::CustomDebug;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+pub struct Handle;
+
+#[derive(CustomDebug)]
+pub struct Client<C> {
+    name: &'static str,
+    #[debug(skip)]
+    cache: C,
+    #[debug(skip)]
+    handle: Handle,
+    #[debug(skip_if = "Option::is_none")]
+    proxy: Option<&'static str>,
+}
+
+#[derive(CustomDebug)]
+pub struct Headers(
+    &'static str,
+    #[debug(skip_if = "HashMap::is_empty")] HashMap<&'static str, &'static str>,
+);
+
+#[derive(CustomDebug)]
+pub enum Event {
+    Connected {
+        #[debug(skip)]
+        handle: Handle,
+        peer: &'static str,
+    },
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    assert_debug::<Client<NotDebug>>();
+
+    let client = |proxy| Client {
+        name: "api",
+        cache: NotDebug,
+        handle: Handle,
+        proxy,
+    };
+    assert_eq!(format!("{:?}", client(None)), r#"Client { name: "api", .. }"#);
+    assert_eq!(
+        format!("{:?}", client(Some("socks5://proxy"))),
+        r#"Client { name: "api", proxy: Some("socks5://proxy"), .. }"#,
+    );
+
+    let mut headers = Headers("request", HashMap::new());
+    assert_eq!(format!("{:?}", headers), r#"Headers("request", ..)"#);
+    headers.1.insert("host", "example.com");
+    assert_eq!(
+        format!("{:?}", headers),
+        r#"Headers("request", {"host": "example.com"})"#,
+    );
+
+    let event = Event::Connected {
+        handle: Handle,
+        peer: "10.0.0.1",
+    };
+    assert_eq!(format!("{:?}", event), r#"Connected { peer: "10.0.0.1", .. }"#);
+}
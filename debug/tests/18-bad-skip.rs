@@ -0,0 +1,20 @@
+// `skip_if` needs a path to a predicate, and a field that's always skipped
+// can't also be formatted or redacted, or skipped twice over.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Client {
+    #[debug(skip_if = "|p| p.is_none()")]
+    proxy: Option<String>,
+    #[debug(skip, redact)]
+    token: String,
+    #[debug(skip, skip_if = "Vec::is_empty")]
+    peers: Vec<String>,
+}
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Id(#[debug(skip)] u64);
+
+fn main() {}
@@ -1,12 +1,22 @@
 #[test]
 fn tests() {
     let t = trybuild::TestCases::new();
-    //t.pass("tests/01-parse.rs");
-    //t.pass("tests/02-impl-debug.rs");
-    //t.pass("tests/03-custom-format.rs");
-    //t.pass("tests/04-type-parameter.rs");
-    //t.pass("tests/05-phantom-data.rs");
-    //t.pass("tests/06-bound-trouble.rs");
-    //t.pass("tests/07-associated-type.rs");
-    //t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/01-parse.rs");
+    t.pass("tests/02-impl-debug.rs");
+    t.pass("tests/03-custom-format.rs");
+    t.pass("tests/04-type-parameter.rs");
+    t.pass("tests/05-phantom-data.rs");
+    t.pass("tests/06-bound-trouble.rs");
+    t.pass("tests/07-associated-type.rs");
+    t.pass("tests/08-escape-hatch.rs");
+    t.compile_fail("tests/09-bad-format.rs");
+    t.pass("tests/10-field-bound.rs");
+    t.compile_fail("tests/11-bad-bound.rs");
+    t.pass("tests/12-enum.rs");
+    t.pass("tests/13-tuple-unit.rs");
+    t.compile_fail("tests/14-bad-transparent.rs");
+    t.pass("tests/15-redact.rs");
+    t.compile_fail("tests/16-bad-redact.rs");
+    t.pass("tests/17-skip.rs");
+    t.compile_fail("tests/18-bad-skip.rs");
 }